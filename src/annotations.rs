@@ -37,6 +37,35 @@ impl AnnotationEnv {
         None
     }
 
+    /// The terms that have annotations, in term order.
+    pub fn annotated_terms(&self) -> impl Iterator<Item = TermId> + '_ {
+        let mut terms: Vec<TermId> = self.annotation_map.keys().copied().collect();
+        terms.sort_by_key(|t| t.index());
+        terms.into_iter()
+    }
+
+    /// Add the annotations and instantiations of another file. A term may
+    /// only have a spec in one of them, so a term with a spec in both is
+    /// returned and nothing is added. Instantiations replace any this
+    /// environment has for the same term, and models only fill in types that
+    /// have none here.
+    pub fn merge(&mut self, other: AnnotationEnv) -> Result<(), TermId> {
+        if let Some(term_id) = other
+            .annotation_map
+            .keys()
+            .filter(|t| self.annotation_map.contains_key(t))
+            .min_by_key(|t| t.index())
+        {
+            return Err(*term_id);
+        }
+        self.annotation_map.extend(other.annotation_map);
+        self.instantiations_map.extend(other.instantiations_map);
+        for (ty, model) in other.model_map {
+            self.model_map.entry(ty).or_insert(model);
+        }
+        Ok(())
+    }
+
     pub fn get_term_signatures_by_name(
         &self,
         termenv: &TermEnv,
@@ -313,7 +342,19 @@ fn signature_to_term_type_signature(sig: &Signature) -> TermTypeSignature {
     }
 }
 
-pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> AnnotationEnv {
+/// Resolve the specs, models, and instantiations in `defs` against the
+/// program's terms and types. Naming a term or type the program doesn't
+/// declare, or giving a term two specs, is an error naming the symbol.
+pub fn parse_annotations(
+    defs: &Defs,
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+) -> Result<AnnotationEnv, String> {
+    let term_id = |ident: &Ident, what: &str| {
+        termenv
+            .get_term_by_name(typeenv, ident)
+            .ok_or_else(|| format!("{} for unknown term `{}`", what, ident.0))
+    };
     let mut annotation_map = HashMap::new();
     let mut model_map = HashMap::new();
 
@@ -326,7 +367,9 @@ pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> A
         match def {
             &ast::Def::Model(Model { ref name, ref val }) => match val {
                 ast::ModelValue::TypeValue(model_type) => {
-                    let type_id = typeenv.get_type_by_name(&name).unwrap();
+                    let type_id = typeenv
+                        .get_type_by_name(&name)
+                        .ok_or_else(|| format!("model for unknown type `{}`", name.0))?;
                     let ir_type = match model_type {
                         ModelType::Int => annotation_ir::Type::Int,
                         ModelType::Bool => annotation_ir::Type::Bool,
@@ -341,7 +384,7 @@ pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> A
                     for (v, e) in vals {
                         let ident = ast::Ident(format!("{}.{}", name.0, v.0), v.1);
                         // dbg!(&ident);
-                        let term_id = termenv.get_term_by_name(typeenv, &ident).unwrap();
+                        let term_id = term_id(&ident, "model")?;
                         let val = spec_to_expr(e, &env);
                         let ty = match val {
                             Expr::Const(Const { ref ty, .. }) => ty,
@@ -377,12 +420,10 @@ pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> A
     for def in &defs.defs {
         match def {
             &ast::Def::Spec(ref spec) => {
-                let term_id = termenv.get_term_by_name(typeenv, &spec.term).unwrap();
-                assert!(
-                    !annotation_map.contains_key(&term_id),
-                    "duplicate spec for {}",
-                    spec.term.0
-                );
+                let term_id = term_id(&spec.term, "spec")?;
+                if annotation_map.contains_key(&term_id) {
+                    return Err(format!("duplicate spec for `{}`", spec.term.0));
+                }
                 let sig = TermSignature {
                     args: spec
                         .args
@@ -436,7 +477,7 @@ pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> A
     for def in &defs.defs {
         match def {
             &ast::Def::Instantiation(ref inst) => {
-                let term_id = termenv.get_term_by_name(typeenv, &inst.term).unwrap();
+                let term_id = term_id(&inst.term, "instantiation")?;
                 let sigs = match &inst.form {
                     Some(form) => forms_map[&form.0].clone(),
                     None => inst
@@ -451,9 +492,9 @@ pub fn parse_annotations(defs: &Defs, termenv: &TermEnv, typeenv: &TypeEnv) -> A
         }
     }
 
-    AnnotationEnv {
+    Ok(AnnotationEnv {
         annotation_map,
         instantiations_map,
         model_map,
-    }
+    })
 }
@@ -0,0 +1,56 @@
+use veri_ir::{ConcreteInput, ConcreteTest, Type};
+
+/// Parse a concrete test: the LHS term name, its argument literals, and the
+/// expected output literal, each on their own line. For example:
+///
+/// ```text
+/// iadd
+/// #x01 #x02
+/// #x03
+/// ```
+///
+/// The type of each input comes from its literal: `#b` and `#x` literals are
+/// bitvectors of the written width, `true`/`false` are booleans, and decimal
+/// numbers are integers.
+pub fn parse_concrete_test(src: &str) -> Result<ConcreteTest, String> {
+    let mut lines = src
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with(';'));
+
+    let termname = lines.next().ok_or("missing term name")?.to_string();
+    let args = lines
+        .next()
+        .ok_or("missing argument line")?
+        .split_whitespace()
+        .map(concrete_input)
+        .collect::<Result<Vec<_>, _>>()?;
+    let output = concrete_input(lines.next().ok_or("missing output line")?)?;
+    if let Some(extra) = lines.next() {
+        return Err(format!("unexpected line: {}", extra));
+    }
+
+    Ok(ConcreteTest {
+        termname,
+        args,
+        output,
+    })
+}
+
+fn concrete_input(literal: &str) -> Result<ConcreteInput, String> {
+    let ty = if let Some(bits) = literal.strip_prefix("#b") {
+        Type::BitVector(Some(bits.len()))
+    } else if let Some(hex) = literal.strip_prefix("#x") {
+        Type::BitVector(Some(hex.len() * 4))
+    } else if literal == "true" || literal == "false" {
+        Type::Bool
+    } else if literal.parse::<i128>().is_ok() {
+        Type::Int
+    } else {
+        return Err(format!("unrecognized literal: {}", literal));
+    };
+    Ok(ConcreteInput {
+        literal: literal.to_string(),
+        ty,
+    })
+}
@@ -0,0 +1,4887 @@
+use clap::ValueEnum;
+use cranelift_isle::sema::{self};
+use cranelift_isle::sema::{Pattern, TermEnv, TermId, TypeEnv, TypeId, VarId};
+use easy_smt::SExprData;
+use easy_smt::{Response, SExpr};
+use itertools::Itertools;
+use rayon::prelude::*;
+use regex::RegexSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, FromRepr};
+
+use crate::annotations::AnnotationEnv;
+use crate::report::{Reporter, Verbosity};
+use crate::termname::pattern_contains_termname;
+use crate::TargetConfig;
+use veri_ir::{annotation_ir, ConcreteTest, Expr, TermSignature, Type};
+
+/* ----- STRUCTS FOR RECURSIVE RULE PARSING, TYPE CONVERSION ----- */
+#[derive(Clone, Debug, Default)]
+struct RuleParseTree {
+    // a map of var name to type variable, where var could be
+    // Pattern::Var or var used in Pattern::BindPattern
+    varid_to_type_var_map: HashMap<VarId, u32>,
+    // a map of type var to value, if known
+    type_var_to_val_map: HashMap<u32, i128>,
+    // bookkeeping that tells the next unused type var
+    next_type_var: u32,
+    // combined constraints from all nodes
+    concrete_constraints: HashSet<TypeExpr>,
+    var_constraints: HashSet<TypeExpr>,
+    bv_constraints: HashSet<TypeExpr>,
+
+    ty_vars: HashMap<veri_ir::Expr, u32>,
+    quantified_vars: HashMap<String, u32>,
+    free_vars: HashMap<String, u32>,
+    assumptions: Vec<Expr>,
+    rhs_assertions: Vec<Expr>,
+    // type vars of values of ISLE's `Unit` type, which carry no value and so
+    // are left untyped
+    unit_vars: HashSet<u32>,
+
+    // widths of the target's registers and flags
+    target: TargetConfig,
+    // whether a shift or rotate amount may be narrower than its value
+    narrow_shift_amounts: bool,
+    // each `conv_to` to a constant width: the annotated term and the type
+    // vars of the operand and result, checked for narrowing in strict mode
+    conv_tos: Vec<(String, u32, u32)>,
+    // each switch: the annotated term, the type var of its control, and its
+    // constant match values in order, or `None` if any match isn't constant
+    switches: Vec<(String, u32, Option<Vec<i128>>)>,
+    // each let binding with a modeled declared type: the bound variable and
+    // the constraint giving it that type, to name the binding if the
+    // constraint conflicts
+    let_decls: Vec<(String, TypeExpr)>,
+    // each bind pattern: the bound variable and the type vars of the
+    // variable, its subpattern, and the bind itself
+    bind_patterns: Vec<(String, u32, u32, u32)>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+// Constraints either assign concrete types to type variables
+// or set them equal to other type variables
+pub enum TypeExpr {
+    // Symbolic Sum for now. This only checks if the sum of bv widths of the lhs match the rhs.
+    // Built with `TypeExpr::symbolic`, which keeps the operands sorted.
+    Symbolic(Vec<u32>, Vec<u32>),
+    Concrete(u32, annotation_ir::Type),
+    Variable(u32, u32),
+    // The type variable of the first arg is equal to the value of the second
+    WidthInt(u32, u32),
+    // As `WidthInt`, but only if the first arg turns out to be a bitvector
+    ConditionalWidthInt(u32, u32),
+    // The width of the first arg is at most the second's, once both are known
+    WidthAtMost(u32, u32),
+    // The type variable, if an integer or bitvector, has this value
+    Value(u32, i128),
+}
+
+impl TypeExpr {
+    // A width sum with its operands sorted, so sums that differ only in
+    // operand order are equal and dedup in a constraint set.
+    fn symbolic(mut l: Vec<u32>, mut r: Vec<u32>) -> Self {
+        l.sort();
+        r.sort();
+        TypeExpr::Symbolic(l, r)
+    }
+
+    // The type variables the constraint mentions, in order.
+    fn type_vars(&self) -> Vec<u32> {
+        match self {
+            TypeExpr::Symbolic(l, r) => l.iter().chain(r).copied().collect(),
+            TypeExpr::Concrete(v, _) | TypeExpr::Value(v, _) => vec![*v],
+            TypeExpr::Variable(u, v)
+            | TypeExpr::WidthInt(u, v)
+            | TypeExpr::ConditionalWidthInt(u, v)
+            | TypeExpr::WidthAtMost(u, v) => vec![*u, *v],
+        }
+    }
+}
+
+impl std::fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sum = |vs: &Vec<u32>| {
+            vs.iter()
+                .map(|v| format!("width(t{})", v))
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        match self {
+            TypeExpr::Symbolic(l, r) => write!(f, "{} == {}", sum(l), sum(r)),
+            TypeExpr::Concrete(v, ty) => write!(f, "t{}: {}", v, type_to_num(ty)),
+            TypeExpr::Variable(u, v) => write!(f, "t{} == t{}", u, v),
+            TypeExpr::WidthInt(v, w) => write!(f, "width(t{}) == value(t{})", v, w),
+            TypeExpr::ConditionalWidthInt(v, w) => {
+                write!(f, "t{} is bv => width(t{}) == value(t{})", v, v, w)
+            }
+            TypeExpr::WidthAtMost(v, x) => write!(f, "width(t{}) <= width(t{})", v, x),
+            TypeExpr::Value(v, n) => write!(f, "value(t{}) == {}", v, n),
+        }
+    }
+}
+
+/// Reasons a rule could not be assigned types.
+#[derive(Debug, Clone)]
+pub enum TypeError {
+    /// The rule's type constraints are unsatisfiable. The core is a subset of
+    /// the rule's constraints that already conflict with each other.
+    Unsat {
+        rule: String,
+        term: String,
+        core: Vec<TypeExpr>,
+    },
+    /// A term's annotation is malformed, e.g. an extract with swapped bounds.
+    InvalidAnnotation { term: String, message: String },
+    /// A width sum (e.g. from a concat) whose widths couldn't be determined.
+    UnknownWidth {
+        rule: String,
+        term: String,
+        constraint: TypeExpr,
+    },
+    /// The inferred types disagree with a concrete test of the rule's term.
+    ConcreteMismatch {
+        rule: String,
+        term: String,
+        message: String,
+    },
+    /// The rule has more than one typing; both solutions are kept so the
+    /// type variables they disagree on can be reported.
+    Ambiguous {
+        rule: String,
+        term: String,
+        first: HashMap<u32, annotation_ir::Type>,
+        second: HashMap<u32, annotation_ir::Type>,
+    },
+    /// The solver gave up on the rule's constraints within the timeout.
+    Timeout { rule: String, term: String, ms: u64 },
+    /// The rule's right-hand side uses a variable the rule doesn't bind.
+    UnboundVar { rule: String, var: usize },
+    /// A term used by the rule has no annotation.
+    UnannotatedTerm { term: String },
+    /// A `let` binding uses itself or a binding after it in the same `let`.
+    CyclicLet {
+        rule: String,
+        var: String,
+        uses: String,
+    },
+    /// A let binding's declared type conflicts with its value's. The core is
+    /// as for `Unsat`.
+    LetTypeMismatch {
+        rule: String,
+        var: String,
+        core: Vec<TypeExpr>,
+    },
+    /// A bind pattern's variable is used at a type that conflicts with the
+    /// type of the pattern it binds.
+    BindMismatch {
+        rule: String,
+        var: String,
+        bound: annotation_ir::Type,
+        pattern: annotation_ir::Type,
+    },
+    /// A solved width is wider than `Config::max_width`.
+    WidthTooLarge {
+        rule: String,
+        term: String,
+        var: u32,
+        width: usize,
+        max: usize,
+    },
+    /// In strict mode, a `conv_to` whose result is narrower than its operand.
+    NarrowingConvTo {
+        rule: String,
+        term: String,
+        from: usize,
+        to: usize,
+    },
+    /// With switch coverage required, a switch on a narrow bitvector that
+    /// doesn't match every value. Only the first few missing values are kept.
+    IncompleteSwitch {
+        rule: String,
+        term: String,
+        width: usize,
+        missing: Vec<i128>,
+    },
+    /// A value of ISLE's `Unit` type is constrained like a value, e.g. by a
+    /// spec that gives the result of a side-effecting term a type.
+    UnitValue {
+        rule: String,
+        term: String,
+        core: Vec<TypeExpr>,
+    },
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::Unsat { rule, term, core } => {
+                write!(
+                    f,
+                    "type constraints for rule `{}` (term `{}`) are unsatisfiable",
+                    rule, term
+                )?;
+                if !core.is_empty() {
+                    write!(f, "; conflicting constraints:")?;
+                    for c in core {
+                        write!(f, "\n\t{}", c)?;
+                    }
+                }
+                Ok(())
+            }
+            TypeError::InvalidAnnotation { term, message } => {
+                write!(f, "invalid annotation for term `{}`: {}", term, message)
+            }
+            TypeError::UnknownWidth {
+                rule,
+                term,
+                constraint,
+            } => write!(
+                f,
+                "widths in `{}` for rule `{}` (term `{}`) could not be determined",
+                constraint, rule, term
+            ),
+            TypeError::ConcreteMismatch {
+                rule,
+                term,
+                message,
+            } => write!(
+                f,
+                "inferred types for rule `{}` (term `{}`) don't match the concrete test: {}",
+                rule, term, message
+            ),
+            TypeError::Ambiguous {
+                rule,
+                term,
+                first,
+                second,
+            } => {
+                write!(
+                    f,
+                    "types for rule `{}` (term `{}`) are ambiguous; two solutions differ at:",
+                    rule, term
+                )?;
+                for (v, ty) in first.iter().sorted_by_key(|(v, _)| **v) {
+                    if let Some(other) = second.get(v).filter(|other| *other != ty) {
+                        write!(
+                            f,
+                            "\n\tt{}: {} or {}",
+                            v,
+                            type_to_num(ty),
+                            type_to_num(other)
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+            TypeError::Timeout { rule, term, ms } => write!(
+                f,
+                "solver timed out after {}ms on rule `{}` (term `{}`)",
+                ms, rule, term
+            ),
+            TypeError::UnboundVar { rule, var } => {
+                write!(f, "rule `{}` uses unbound variable v{}", rule, var)
+            }
+            TypeError::UnannotatedTerm { term } => {
+                write!(f, "term `{}` has no annotation", term)
+            }
+            TypeError::CyclicLet { rule, var, uses } => write!(
+                f,
+                "rule `{}` binds `{}` in a let using `{}`, which isn't bound yet",
+                rule, var, uses
+            ),
+            TypeError::LetTypeMismatch { rule, var, core } => {
+                write!(
+                    f,
+                    "rule `{}` binds `{}` in a let with a declared type that conflicts with its value",
+                    rule, var
+                )?;
+                write!(f, "; conflicting constraints:")?;
+                for c in core {
+                    write!(f, "\n\t{}", c)?;
+                }
+                Ok(())
+            }
+            TypeError::WidthTooLarge {
+                rule,
+                term,
+                var,
+                width,
+                max,
+            } => write!(
+                f,
+                "width {} of t{} in rule `{}` (term `{}`) exceeds --max-width {}; likely underconstrained",
+                width, var, rule, term, max
+            ),
+            TypeError::BindMismatch {
+                rule,
+                var,
+                bound,
+                pattern,
+            } => write!(
+                f,
+                "bind pattern variable `{}` of rule `{}`: bound type {} conflicts with pattern type {}",
+                var,
+                rule,
+                type_to_num(bound),
+                type_to_num(pattern)
+            ),
+            TypeError::NarrowingConvTo {
+                rule,
+                term,
+                from,
+                to,
+            } => write!(
+                f,
+                "rule `{}` narrows bv{} to bv{} with conv_to in term `{}`",
+                rule, from, to, term
+            ),
+            TypeError::IncompleteSwitch {
+                rule,
+                term,
+                width,
+                missing,
+            } => write!(
+                f,
+                "switch on bv{} in term `{}` of rule `{}` doesn't match {}",
+                width,
+                term,
+                rule,
+                missing.iter().join(", ")
+            ),
+            TypeError::UnitValue { rule, term, core } => {
+                write!(
+                    f,
+                    "rule `{}` (term `{}`) types a value of ISLE's `Unit` type",
+                    rule, term
+                )?;
+                for c in core {
+                    write!(f, "\n\t{}", c)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnnotationTypeInfo {
+    // map of annotation variable to assigned type var
+    pub term: String,
+    pub var_to_type_var: HashMap<String, u32>,
+    // name of the annotation's return variable
+    pub ret: String,
+}
+
+#[derive(Debug)]
+pub struct RuleSemantics {
+    pub annotation_infos: Vec<AnnotationTypeInfo>,
+
+    // map of type var to solved type; values of ISLE's `Unit` type have none
+    pub type_var_to_type: HashMap<u32, annotation_ir::Type>,
+
+    pub lhs: veri_ir::Expr,
+    pub rhs: veri_ir::Expr,
+
+    pub quantified_vars: Vec<veri_ir::BoundVar>,
+    pub free_vars: Vec<veri_ir::BoundVar>,
+    pub assumptions: Vec<Expr>,
+    pub rhs_assertions: Vec<Expr>,
+
+    // the rule as parseable ISLE with its types in comments, if
+    // `Config::emit_isle` is set
+    pub isle: Option<String>,
+
+    // the rule's type constraints as a Graphviz DOT graph, if
+    // `Config::emit_dot` is set
+    pub dot: Option<String>,
+
+    // the size of the rule's constraint problem and how long it took to
+    // solve, if `Config::stats` is set
+    pub stats: Option<RuleStats>,
+
+    // the names and constraints of each type var, if `Config::explain` is
+    // set
+    pub provenance: Option<HashMap<u32, Provenance>>,
+
+    // the bitvectors whose width is an integer's value, by type var
+    pub width_links: Vec<WidthLink>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RuleStats {
+    pub type_vars: u32,
+    pub concrete_constraints: usize,
+    pub var_constraints: usize,
+    pub bv_constraints: usize,
+    pub solve_time: Duration,
+}
+
+/// A bitvector whose width is the value of an integer, from a `WidthInt`
+/// constraint, or a `ConditionalWidthInt` one whose first type var solved to
+/// a bitvector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WidthLink {
+    /// The bitvector's type var
+    pub bv: u32,
+    /// The integer's type var
+    pub int: u32,
+    /// The bitvector's solved width, if the solution fixes it
+    pub width: Option<usize>,
+}
+
+/// Where a type variable's solved type came from.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance {
+    /// The rule and annotation variables the type variable was made for
+    pub names: Vec<String>,
+    /// Every constraint that mentions the type variable
+    pub constraints: Vec<TypeExpr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypeVarNode {
+    ident: String,
+    construct: TypeVarConstruct,
+    type_var: u32,
+    children: Vec<TypeVarNode>,
+    assertions: Vec<veri_ir::Expr>,
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeVarConstruct {
+    Var,
+    BindPattern,
+    Wildcard(u32),
+    Term(TermId),
+    Const(i128),
+    // `true` or `false`, which are booleans whatever their declared type
+    BoolConst(bool),
+    // A constant with no known value, typed by its declared ISLE type
+    OpaqueConst(TypeId),
+    // The identifiers of a let's bindings with their declared types
+    Let(Vec<(String, TypeId)>),
+    And,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SolverKind {
+    Z3,
+    Cvc5,
+    Bitwuzla,
+}
+
+impl SolverKind {
+    /// The program and arguments used to launch the solver in SMT-LIB mode.
+    fn command(&self) -> (&'static str, Vec<&'static str>) {
+        match self {
+            SolverKind::Z3 => ("z3", vec!["-smt2", "-in"]),
+            SolverKind::Cvc5 => (
+                "cvc5",
+                vec![
+                    "--quiet",
+                    "--lang=smt2",
+                    "--incremental",
+                    "--produce-models",
+                ],
+            ),
+            SolverKind::Bitwuzla => ("bitwuzla", vec!["--produce-models", "--lang", "smt2"]),
+        }
+    }
+
+    /// The option that bounds the time of each check, in milliseconds.
+    fn timeout_option(&self) -> &'static str {
+        match self {
+            SolverKind::Z3 => ":timeout",
+            SolverKind::Cvc5 => ":tlimit-per",
+            SolverKind::Bitwuzla => ":time-limit-per",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    /// Which LHS root to verify
+    pub term: String,
+    /// Which named rule to verify
+    pub names: Option<Vec<String>>,
+    /// Match `names` as regexes rather than exact rule names
+    pub names_regex: bool,
+    /// Which named rules to skip
+    pub exclude_names: Vec<String>,
+    /// Where to write the SMT replay log, if anywhere
+    pub smt_log: Option<String>,
+    /// Which SMT solver to use
+    pub solver: SolverKind,
+    /// Type rules in parallel, with a solver per worker thread
+    pub parallel: bool,
+    /// Where typed rules, skipped rules, and typing progress are printed
+    pub reporter: Reporter,
+    /// Solver time limit per rule, in milliseconds
+    pub timeout: Option<u64>,
+    /// Check that each rule's typing is unique
+    pub check_unique: bool,
+    /// Print each rule's constraints before solving them
+    pub dump_constraints: bool,
+    /// Render each typed rule as parseable ISLE into `RuleSemantics::isle`
+    pub emit_isle: bool,
+    /// Render each typed rule's constraints as DOT into `RuleSemantics::dot`
+    pub emit_dot: bool,
+    /// Type unannotated terms with one argument of their return type as the
+    /// identity, rather than skipping their rules
+    pub assume_identity: bool,
+    /// Record each typed rule's constraint counts and solve time into
+    /// `RuleSemantics::stats`
+    pub stats: bool,
+    /// Register and flags widths of the target
+    pub target: TargetConfig,
+    /// Reject rules with a `conv_to` to a constant width narrower than its
+    /// operand, which is usually a mistake for an extract
+    pub strict_conv_to: bool,
+    /// Reject rules with a switch on a bitvector at most this wide that
+    /// doesn't match every value
+    pub switch_coverage: Option<usize>,
+    /// Let a shift or rotate amount be narrower than the value it moves, as
+    /// an immediate often is, rather than the same width
+    pub narrow_shift_amounts: bool,
+    /// Reject rules typed with a bitvector wider than this, 512 on the
+    /// command line
+    pub max_width: usize,
+    /// Type unannotated terms without arguments as unknown values of any
+    /// type, rather than skipping their rules
+    pub poly_nullary: bool,
+    /// Print how the type variables with this name, or this `tN` type
+    /// variable, got their types, and record `RuleSemantics::provenance`
+    pub explain: Option<String>,
+}
+
+/* ----- CONVERT AST TO RULE SEMANTICS ----- */
+
+/// Convert a solved annotation type to the VIR type the verifier sees.
+///
+/// This fails where VIR has no exact equivalent: a bitvector of unknown
+/// width, which would lose the class it shares with other unknown widths, and
+/// a polymorphic type. For the rest it is the inverse of
+/// `annotation_type_for_vir_type`:
+///
+/// ```
+/// use type_inf::inference::{annotation_type_for_vir_type, vir_type_for_annotation_type};
+/// use veri_ir::annotation_ir::Type;
+///
+/// for ty in [Type::BitVector, Type::BitVectorWithWidth(8), Type::Int, Type::Bool] {
+///     let vir = vir_type_for_annotation_type(&ty).unwrap();
+///     assert_eq!(annotation_type_for_vir_type(&vir), ty);
+/// }
+/// assert!(vir_type_for_annotation_type(&Type::Poly(0)).is_err());
+/// ```
+pub fn vir_type_for_annotation_type(
+    aty: &annotation_ir::Type,
+) -> Result<veri_ir::Type, LossyTypeConversion> {
+    match aty {
+        annotation_ir::Type::BitVector => Ok(veri_ir::Type::BitVector(None)),
+        annotation_ir::Type::BitVectorWithWidth(w) => Ok(veri_ir::Type::BitVector(Some(*w))),
+        annotation_ir::Type::Int => Ok(veri_ir::Type::Int),
+        annotation_ir::Type::Bool => Ok(veri_ir::Type::Bool),
+        annotation_ir::Type::BitVectorUnknown(..) | annotation_ir::Type::Poly(_) => {
+            Err(LossyTypeConversion(aty.clone()))
+        }
+    }
+}
+
+/// Convert a solved annotation type to the VIR type the verifier sees,
+/// accepting the conversions `vir_type_for_annotation_type` rejects: a
+/// bitvector of unknown width or a polymorphic type becomes a bitvector of
+/// unknown width.
+pub fn vir_type_for_annotation_type_lossy(aty: &annotation_ir::Type) -> veri_ir::Type {
+    match aty {
+        annotation_ir::Type::BitVectorUnknown(..) | annotation_ir::Type::Poly(_) => {
+            veri_ir::Type::BitVector(None)
+        }
+        _ => vir_type_for_annotation_type(aty).unwrap(),
+    }
+}
+
+/// An annotation type with no exact VIR equivalent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LossyTypeConversion(pub annotation_ir::Type);
+
+impl std::fmt::Display for LossyTypeConversion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "type {} has no exact VIR equivalent",
+            type_to_num(&self.0)
+        )
+    }
+}
+
+impl std::error::Error for LossyTypeConversion {}
+
+/// The label of a solved type in a displayed rule. Every type has its own
+/// label, so a bitvector whose width is unknown is distinguishable from a
+/// polymorphic one:
+///
+/// ```
+/// use type_inf::inference::type_to_num;
+/// use veri_ir::annotation_ir::Type;
+///
+/// assert_eq!(type_to_num(&Type::BitVector), "bv");
+/// assert_eq!(type_to_num(&Type::BitVectorUnknown(0)), "bvunk");
+/// assert_eq!(type_to_num(&Type::Poly(0)), "poly");
+/// ```
+pub fn type_to_num(aty: &annotation_ir::Type) -> String {
+    match aty {
+        annotation_ir::Type::BitVectorUnknown(..) => "bvunk".to_string(),
+        annotation_ir::Type::BitVector => "bv".to_string(),
+        annotation_ir::Type::BitVectorWithWidth(w) => format!("bv{}", &w),
+        annotation_ir::Type::Int => "int".to_string(),
+        annotation_ir::Type::Bool => "bool".to_string(),
+        annotation_ir::Type::Poly(_) => "poly".to_string(),
+    }
+}
+
+/// Convert a VIR type to the annotation type with the same meaning. Every VIR
+/// type has one, so this never loses information.
+pub fn annotation_type_for_vir_type(ty: &Type) -> annotation_ir::Type {
+    match ty {
+        Type::BitVector(Some(x)) => annotation_ir::Type::BitVectorWithWidth(*x),
+        Type::BitVector(None) => annotation_ir::Type::BitVector,
+        Type::Bool => annotation_ir::Type::Bool,
+        Type::Int => annotation_ir::Type::Int,
+    }
+}
+
+/// Infer types for every rule selected by `config` at one instantiation of
+/// the root term's signature, using a fresh solver.
+pub fn infer_types(
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    config: &Config,
+    types: &TermSignature,
+) -> HashMap<sema::RuleId, RuleSemantics> {
+    typed_rules(try_infer_types(
+        termenv,
+        typeenv,
+        annotation_env,
+        config,
+        types,
+    ))
+}
+
+/// As `infer_types`, but keeping why each rule that couldn't be typed failed.
+pub fn try_infer_types(
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    config: &Config,
+    types: &TermSignature,
+) -> HashMap<sema::RuleId, Result<RuleSemantics, TypeError>> {
+    let mut solver = TypeSolver::new(config);
+    try_type_rules_with_term_and_types(
+        &mut solver,
+        termenv,
+        typeenv,
+        annotation_env,
+        config,
+        types,
+        &None,
+    )
+}
+
+pub fn type_rules_with_term_and_types(
+    solver: &mut TypeSolver,
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    config: &Config,
+    types: &TermSignature,
+    concrete: &Option<ConcreteTest>,
+) -> HashMap<sema::RuleId, RuleSemantics> {
+    typed_rules(try_type_rules_with_term_and_types(
+        solver,
+        termenv,
+        typeenv,
+        annotation_env,
+        config,
+        types,
+        concrete,
+    ))
+}
+
+// The rules that were typed, dropping those that couldn't be, which were
+// already reported.
+fn typed_rules(
+    results: HashMap<sema::RuleId, Result<RuleSemantics, TypeError>>,
+) -> HashMap<sema::RuleId, RuleSemantics> {
+    results
+        .into_iter()
+        .filter_map(|(id, result)| Some((id, result.ok()?)))
+        .collect()
+}
+
+/// As `type_rules_with_term_and_types`, but keeping why each rule that
+/// couldn't be typed failed. Failures are still reported as they happen.
+pub fn try_type_rules_with_term_and_types(
+    solver: &mut TypeSolver,
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    config: &Config,
+    types: &TermSignature,
+    concrete: &Option<ConcreteTest>,
+) -> HashMap<sema::RuleId, Result<RuleSemantics, TypeError>> {
+    let rules = selected_rules(termenv, typeenv, config);
+
+    if config.parallel {
+        // `map_init` starts a solver process for each piece rayon splits the
+        // rules into, reused for every rule in the piece. Pieces are kept at
+        // least a thread's share of the rules long, so there are about as
+        // many solvers as threads. Workers don't write the replay log, since
+        // their interleaved commands wouldn't replay.
+        let worker_config = Config {
+            smt_log: None,
+            reporter: config.reporter.without_trace(),
+            ..config.clone()
+        };
+        let share = rules.len().div_ceil(rayon::current_num_threads()).max(1);
+        return rules
+            .par_iter()
+            .with_min_len(share)
+            .map_init(
+                || TypeSolver::new(&worker_config),
+                |solver, rule| {
+                    let result = type_annotations_using_rule(
+                        rule,
+                        solver,
+                        annotation_env,
+                        typeenv,
+                        termenv,
+                        &worker_config,
+                        types,
+                        concrete,
+                    );
+                    report_skipped(rule, typeenv, &worker_config.reporter, &result);
+                    (rule.id, result)
+                },
+            )
+            .collect();
+    }
+
+    let mut results = HashMap::new();
+    for rule in rules {
+        let result = type_annotations_using_rule(
+            rule,
+            solver,
+            annotation_env,
+            typeenv,
+            termenv,
+            config,
+            types,
+            concrete,
+        );
+        report_skipped(rule, typeenv, &config.reporter, &result);
+        results.insert(rule.id, result);
+    }
+    results
+}
+
+// Report why a rule couldn't be typed, ending any partial trace line.
+fn report_skipped(
+    rule: &sema::Rule,
+    typeenv: &TypeEnv,
+    reporter: &Reporter,
+    result: &Result<RuleSemantics, TypeError>,
+) {
+    if let Err(err) = result {
+        reporter.trace("\n");
+        reporter.failure(format_args!(
+            "Skipping rule `{}` at {}: {}",
+            rule_name(rule, typeenv),
+            rule_location(rule, typeenv),
+            err
+        ));
+    }
+}
+
+/// The rules `config` selects for typing: those rooted at its term and
+/// matching its rule names.
+pub fn selected_rules<'a>(
+    termenv: &'a TermEnv,
+    typeenv: &TypeEnv,
+    config: &Config,
+) -> Vec<&'a sema::Rule> {
+    let name_patterns = name_patterns(config);
+    termenv
+        .rules
+        .iter()
+        .filter(|rule| rule_is_selected(rule, termenv, typeenv, config, &name_patterns))
+        .collect()
+}
+
+// The `names` of a config compiled as regexes, if `names_regex` is set. Each
+// pattern must match the whole rule name.
+fn name_patterns(config: &Config) -> Option<RegexSet> {
+    if !config.names_regex {
+        return None;
+    }
+    let names = config.names.as_ref()?;
+    let anchored = names.iter().map(|n| format!("^(?:{})$", n));
+    Some(RegexSet::new(anchored).unwrap_or_else(|err| panic!("Bad rule name regex: {}", err)))
+}
+
+fn rule_is_selected(
+    rule: &sema::Rule,
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    config: &Config,
+    name_patterns: &Option<RegexSet>,
+) -> bool {
+    // Only type rules with the given term on the LHS
+    if !pattern_contains_termname(
+        // Hack for now: typeid not used
+        &Pattern::Term(
+            cranelift_isle::sema::TypeId(0),
+            rule.root_term,
+            rule.args.clone(),
+        ),
+        &config.term,
+        termenv,
+        typeenv,
+    ) {
+        return false;
+    }
+    if let Some(names) = &config.names {
+        if rule.name.is_none() {
+            return false;
+        }
+        let name = &typeenv.syms[rule.name.unwrap().index()];
+        let selected = match name_patterns {
+            Some(patterns) => patterns.is_match(name),
+            None => names.contains(name),
+        };
+        if !selected {
+            return false;
+        }
+    }
+    // Unnamed rules can't be excluded by name
+    if let Some(name) = rule.name {
+        if config.exclude_names.contains(&typeenv.syms[name.index()]) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Annotation coverage of the terms used by the rules selected by a config.
+#[derive(Debug, Default)]
+pub struct AnnotationCoverage {
+    pub annotated: Vec<String>,
+    pub unannotated: Vec<String>,
+}
+
+/// Collect every term appearing in the selected rules (patterns, if-lets, and
+/// right-hand sides) and split them by whether they have an annotation.
+pub fn annotation_coverage(
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    config: &Config,
+) -> AnnotationCoverage {
+    fn pattern_terms(pat: &Pattern, terms: &mut HashSet<TermId>) {
+        match pat {
+            Pattern::Term(_, term_id, args) => {
+                terms.insert(*term_id);
+                args.iter().for_each(|a| pattern_terms(a, terms));
+            }
+            Pattern::BindPattern(_, _, subpat) => pattern_terms(subpat, terms),
+            Pattern::And(_, subpats) => subpats.iter().for_each(|p| pattern_terms(p, terms)),
+            Pattern::Var(..)
+            | Pattern::Wildcard(_)
+            | Pattern::ConstPrim(..)
+            | Pattern::ConstInt(..) => {}
+        }
+    }
+    fn expr_terms(expr: &sema::Expr, terms: &mut HashSet<TermId>) {
+        match expr {
+            sema::Expr::Term(_, term_id, args) => {
+                terms.insert(*term_id);
+                args.iter().for_each(|a| expr_terms(a, terms));
+            }
+            sema::Expr::Let { bindings, body, .. } => {
+                for (_, _, e) in bindings {
+                    expr_terms(e, terms);
+                }
+                expr_terms(body, terms);
+            }
+            sema::Expr::Var(..) | sema::Expr::ConstPrim(..) | sema::Expr::ConstInt(..) => {}
+        }
+    }
+
+    let name_patterns = name_patterns(config);
+    let mut terms = HashSet::new();
+    for rule in termenv
+        .rules
+        .iter()
+        .filter(|rule| rule_is_selected(rule, termenv, typeenv, config, &name_patterns))
+    {
+        terms.insert(rule.root_term);
+        rule.args.iter().for_each(|a| pattern_terms(a, &mut terms));
+        for iflet in &rule.iflets {
+            pattern_terms(&iflet.lhs, &mut terms);
+            expr_terms(&iflet.rhs, &mut terms);
+        }
+        expr_terms(&rule.rhs, &mut terms);
+    }
+
+    let mut coverage = AnnotationCoverage::default();
+    for term_id in terms {
+        let name = typeenv.syms[termenv.terms[term_id.index()].name.index()].clone();
+        if annotation_env.get_annotation_for_term(&term_id).is_some() {
+            coverage.annotated.push(name);
+        } else {
+            coverage.unannotated.push(name);
+        }
+    }
+    coverage.annotated.sort();
+    coverage.unannotated.sort();
+    coverage
+}
+
+fn type_annotations_using_rule<'a>(
+    rule: &'a sema::Rule,
+    solver: &mut TypeSolver,
+    annotation_env: &'a AnnotationEnv,
+    typeenv: &'a TypeEnv,
+    termenv: &'a TermEnv,
+    config: &Config,
+    types: &TermSignature,
+    concrete: &'a Option<ConcreteTest>,
+) -> Result<RuleSemantics, TypeError> {
+    let term = &config.term;
+    let reporter = &config.reporter;
+    let mut parse_tree = RuleParseTree {
+        varid_to_type_var_map: HashMap::new(),
+        type_var_to_val_map: HashMap::new(),
+        next_type_var: 1,
+        concrete_constraints: HashSet::new(),
+        var_constraints: HashSet::new(),
+        bv_constraints: HashSet::new(),
+        ty_vars: HashMap::new(),
+        quantified_vars: HashMap::new(),
+        free_vars: HashMap::new(),
+        assumptions: vec![],
+        rhs_assertions: vec![],
+        unit_vars: HashSet::new(),
+        target: config.target,
+        narrow_shift_amounts: config.narrow_shift_amounts,
+        conv_tos: vec![],
+        switches: vec![],
+        let_decls: vec![],
+        bind_patterns: vec![],
+    };
+    let location = rule_location(rule, typeenv);
+    let mut annotation_infos = vec![];
+    // Parse trees of each if-let's pattern and expression, kept for display
+    let mut iflet_nodes = vec![];
+    if !rule.iflets.is_empty() {
+        reporter.trace("\n\tif-lets:");
+        for iflet in &rule.iflets {
+            let mut iflet_lhs = create_parse_tree_pattern(
+                rule,
+                &iflet.lhs,
+                &mut parse_tree,
+                typeenv,
+                termenv,
+                term,
+                types,
+            );
+            let mut iflet_rhs =
+                create_parse_tree_expr(rule, &iflet.rhs, &mut parse_tree, typeenv, termenv)?;
+
+            let iflet_lhs_expr = add_rule_constraints(
+                &mut parse_tree,
+                &mut iflet_lhs,
+                termenv,
+                typeenv,
+                annotation_env,
+                &mut annotation_infos,
+                config,
+                false,
+            )?;
+
+            let iflet_rhs_expr = add_rule_constraints(
+                &mut parse_tree,
+                &mut iflet_rhs,
+                termenv,
+                typeenv,
+                annotation_env,
+                &mut annotation_infos,
+                config,
+                false,
+            )?;
+            parse_tree
+                .var_constraints
+                .insert(TypeExpr::Variable(iflet_lhs.type_var, iflet_rhs.type_var));
+            parse_tree.assumptions.push(veri_ir::Expr::Binary(
+                veri_ir::BinaryOp::Eq,
+                Box::new(iflet_lhs_expr),
+                Box::new(iflet_rhs_expr),
+            ));
+            iflet_nodes.push((iflet_lhs, iflet_rhs));
+        }
+        reporter.trace("\n");
+    }
+
+    let lhs = &mut create_parse_tree_pattern(
+        rule,
+        // Hack for now: typeid not used
+        &sema::Pattern::Term(
+            cranelift_isle::sema::TypeId(0),
+            rule.root_term,
+            rule.args.clone(),
+        ),
+        &mut parse_tree,
+        typeenv,
+        termenv,
+        term,
+        types,
+    );
+    let rhs = &mut create_parse_tree_expr(rule, &rule.rhs, &mut parse_tree, typeenv, termenv)?;
+
+    reporter.trace(format_args!("Typing rule at {}:\n\tLHS:", location));
+    let lhs_expr = add_rule_constraints(
+        &mut parse_tree,
+        lhs,
+        termenv,
+        typeenv,
+        annotation_env,
+        &mut annotation_infos,
+        config,
+        false,
+    )?;
+    reporter.trace("\n\tRHS:");
+    let rhs_expr = add_rule_constraints(
+        &mut parse_tree,
+        rhs,
+        termenv,
+        typeenv,
+        annotation_env,
+        &mut annotation_infos,
+        config,
+        true,
+    )?;
+    reporter.trace("\n");
+
+    parse_tree
+        .var_constraints
+        .insert(TypeExpr::Variable(lhs.type_var, rhs.type_var));
+
+    // A repeated match value is legal but its later arms are unreachable
+    for (switch_term, _, values) in &parse_tree.switches {
+        let repeated = values.iter().flatten().duplicates().join(", ");
+        if !repeated.is_empty() {
+            reporter.info(format_args!(
+                "Warning: switch in term `{}` of rule `{}` at {} matches {} more than once",
+                switch_term,
+                rule_name(rule, typeenv),
+                location,
+                repeated
+            ));
+        }
+    }
+
+    let names = if config.dump_constraints || config.emit_dot || config.explain.is_some() {
+        rule_type_var_names(lhs, rhs, &iflet_nodes, &annotation_infos)
+    } else {
+        HashMap::new()
+    };
+    if config.dump_constraints {
+        reporter.result(dump_constraints(
+            &rule_name(rule, typeenv),
+            &parse_tree,
+            &names,
+        ));
+    }
+
+    check_bind_patterns(&rule_name(rule, typeenv), &parse_tree)?;
+
+    // NOTE: This is where SMT Solver should be called
+    let solve_start = Instant::now();
+    let solved = solve_constraints(
+        solver,
+        &rule_name(rule, typeenv),
+        term,
+        &parse_tree.concrete_constraints,
+        &parse_tree.var_constraints,
+        &parse_tree.bv_constraints,
+        &mut parse_tree.type_var_to_val_map,
+        &mut parse_tree.unit_vars,
+        &lhs_expr,
+        &rhs_expr,
+        // Some(&parse_tree.ty_vars),
+    );
+    let stats = config.stats.then(|| RuleStats {
+        // Type variables are numbered from 1
+        type_vars: parse_tree.next_type_var - 1,
+        concrete_constraints: parse_tree.concrete_constraints.len(),
+        var_constraints: parse_tree.var_constraints.len(),
+        bv_constraints: parse_tree.bv_constraints.len(),
+        solve_time: solve_start.elapsed(),
+    });
+    let checked = solved
+        .map_err(|err| blame_let_decl(err, &parse_tree.let_decls))
+        .and_then(|(solution, width_links)| {
+            if let Some(test) = concrete {
+                check_concrete_test(test, lhs, &solution).map_err(|message| {
+                    TypeError::ConcreteMismatch {
+                        rule: rule_name(rule, typeenv),
+                        term: term.clone(),
+                        message,
+                    }
+                })?;
+            }
+            if config.strict_conv_to {
+                check_conv_tos(&rule_name(rule, typeenv), &parse_tree.conv_tos, &solution)?;
+            }
+            if let Some(max_width) = config.switch_coverage {
+                check_switches(
+                    &rule_name(rule, typeenv),
+                    &parse_tree.switches,
+                    max_width,
+                    &solution,
+                )?;
+            }
+            Ok((solution, width_links))
+        });
+    let (solution, width_links) = checked?;
+
+    if reporter.verbosity >= Verbosity::Quiet && !reporter.only_failing {
+        let lhs = solver.display_isle_pattern(
+            termenv,
+            typeenv,
+            rule,
+            &annotation_infos,
+            &[],
+            &solution,
+            &parse_tree.unit_vars,
+            DisplayStyle::Typed,
+            &Pattern::Term(
+                cranelift_isle::sema::TypeId(0),
+                rule.root_term,
+                rule.args.clone(),
+            ),
+            lhs,
+            None,
+        );
+        let rhs = solver.display_isle_expr(
+            termenv,
+            typeenv,
+            rule,
+            &annotation_infos,
+            &[],
+            &solution,
+            &parse_tree.unit_vars,
+            DisplayStyle::Typed,
+            &rule.rhs,
+            rhs,
+            None,
+        );
+        // Print the typed rule at once so parallel output doesn't interleave.
+        reporter.result(format_args!(
+            "{}\n=>\n{}",
+            solver.smt.display(lhs),
+            solver.smt.display(rhs)
+        ));
+    }
+
+    let dot = if config.emit_dot {
+        Some(constraint_graph(
+            &rule_name(rule, typeenv),
+            &parse_tree,
+            &names,
+        ))
+    } else {
+        None
+    };
+
+    let isle = if config.emit_isle {
+        Some(solver.display_isle_rule(
+            termenv,
+            typeenv,
+            rule,
+            &annotation_infos,
+            &solution,
+            &parse_tree.unit_vars,
+            lhs,
+            &iflet_nodes,
+            rhs,
+        ))
+    } else {
+        None
+    };
+
+    let provenance = config.explain.as_ref().map(|query| {
+        let provenance = type_var_provenance(&parse_tree, &names);
+        reporter.result(explain_type_vars(
+            &rule_name(rule, typeenv),
+            query,
+            &provenance,
+            &solution,
+        ));
+        provenance
+    });
+
+    let mut tymap = HashMap::new();
+
+    // Values of ISLE's `Unit` type are the only ones left untyped
+    for (expr, t) in &parse_tree.ty_vars {
+        if let Some(ty) = solution.get(&t) {
+            tymap.insert(*t, vir_type_for_annotation_type_lossy(ty));
+        } else if !parse_tree.unit_vars.contains(t) {
+            panic!(
+                "missing type variable {} in solution for {:?} in rule at {}",
+                t, expr, location
+            );
+        }
+    }
+    let mut quantified_vars = vec![];
+    for (s, t) in parse_tree.quantified_vars.iter().sorted() {
+        let expr = veri_ir::Expr::Terminal(veri_ir::Terminal::Var(s.clone()));
+        if let Some(ty) = solution.get(t) {
+            let ty = vir_type_for_annotation_type_lossy(ty);
+            parse_tree.ty_vars.insert(expr, *t);
+            tymap.insert(*t, ty.clone());
+            quantified_vars.push(veri_ir::BoundVar {
+                name: s.clone(),
+                tyvar: *t,
+            });
+        } else if !parse_tree.unit_vars.contains(t) {
+            panic!(
+                "missing type variable {} in solution for {:?} in rule at {}",
+                t, expr, location
+            );
+        }
+    }
+    let mut free_vars = vec![];
+    for (s, t) in parse_tree.free_vars {
+        let expr = veri_ir::Expr::Terminal(veri_ir::Terminal::Var(s.clone()));
+        if let Some(ty) = solution.get(&t) {
+            let ty = vir_type_for_annotation_type_lossy(ty);
+            parse_tree.ty_vars.insert(expr, t);
+            tymap.insert(t, ty.clone());
+            free_vars.push(veri_ir::BoundVar { name: s, tyvar: t });
+        } else if !parse_tree.unit_vars.contains(&t) {
+            panic!(
+                "missing type variable {} in solution for {:?} in rule at {}",
+                t, expr, location
+            );
+        }
+    }
+
+    Ok(RuleSemantics {
+        annotation_infos,
+        type_var_to_type: solution,
+        lhs: lhs_expr,
+        rhs: rhs_expr,
+        quantified_vars,
+        free_vars,
+        assumptions: parse_tree.assumptions,
+        rhs_assertions: parse_tree.rhs_assertions,
+        isle,
+        dot,
+        stats,
+        provenance,
+        width_links,
+    })
+}
+
+// The names of each type variable and the constraints that mention it.
+fn type_var_provenance(
+    tree: &RuleParseTree,
+    names: &HashMap<u32, String>,
+) -> HashMap<u32, Provenance> {
+    let mut provenance: HashMap<u32, Provenance> = HashMap::new();
+    for c in tree
+        .concrete_constraints
+        .iter()
+        .chain(&tree.var_constraints)
+        .chain(&tree.bv_constraints)
+        .sorted_by_key(|c| c.to_string())
+    {
+        for v in c.type_vars().into_iter().unique() {
+            provenance.entry(v).or_default().constraints.push(c.clone());
+        }
+    }
+    let named = names
+        .iter()
+        .chain(tree.quantified_vars.iter().map(|(name, v)| (v, name)))
+        .chain(tree.free_vars.iter().map(|(name, v)| (v, name)));
+    for (v, name) in named {
+        let names = &mut provenance.entry(*v).or_default().names;
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    for p in provenance.values_mut() {
+        p.names.sort();
+    }
+    provenance
+}
+
+// The type variables matching `query`, either `tN` or a name, with their
+// solved types and the constraints that fixed them. A name matches a parse
+// tree identifier like `x__clif0__3` by the part before its first `__`, with
+// its underscores unescaped.
+fn explain_type_vars(
+    rule: &str,
+    query: &str,
+    provenance: &HashMap<u32, Provenance>,
+    solution: &HashMap<u32, annotation_ir::Type>,
+) -> String {
+    let matches = |v: u32, p: &Provenance| {
+        query == format!("t{}", v)
+            || p.names
+                .iter()
+                .any(|n| n == query || unescaped_head(n) == query)
+    };
+    let mut out = format!("Explaining `{}` in rule `{}`:\n", query, rule);
+    let found = provenance
+        .iter()
+        .filter(|(v, p)| matches(**v, p))
+        .sorted_by_key(|(v, _)| **v)
+        .collect::<Vec<_>>();
+    if found.is_empty() {
+        out.push_str("  no matching type variables\n");
+    }
+    for (v, p) in found {
+        let ty = solution
+            .get(v)
+            .map(type_to_num)
+            .unwrap_or_else(|| "unsolved".to_string());
+        out.push_str(&format!("  t{}: {} ({})\n", v, ty, p.names.join(", ")));
+        for c in &p.constraints {
+            out.push_str(&format!("    {}\n", c));
+        }
+    }
+    out
+}
+
+// A readable name for each type variable of a rule: its parse tree
+// identifier, or the annotation variable it was introduced for.
+fn rule_type_var_names(
+    lhs: &TypeVarNode,
+    rhs: &TypeVarNode,
+    iflets: &[(TypeVarNode, TypeVarNode)],
+    annotation_infos: &[AnnotationTypeInfo],
+) -> HashMap<u32, String> {
+    fn node_names(node: &TypeVarNode, names: &mut HashMap<u32, String>) {
+        names
+            .entry(node.type_var)
+            .or_insert_with(|| node.ident.clone());
+        for child in &node.children {
+            node_names(child, names);
+        }
+    }
+
+    let mut names = HashMap::new();
+    for node in [lhs, rhs]
+        .into_iter()
+        .chain(iflets.iter().flat_map(|(l, r)| [l, r]))
+    {
+        node_names(node, &mut names);
+    }
+    for info in annotation_infos {
+        for (var, t) in &info.var_to_type_var {
+            names
+                .entry(*t)
+                .or_insert_with(|| format!("{}.{}", info.term, var));
+        }
+    }
+    names
+}
+
+// The rule's constraints as a DOT graph: a node per type variable, labeled
+// with its name and concrete types, and an edge per equality. Width
+// constraints are dashed edges.
+fn constraint_graph(rule: &str, tree: &RuleParseTree, names: &HashMap<u32, String>) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut labels: HashMap<u32, Vec<String>> = HashMap::new();
+    let mut edges = vec![];
+    for c in tree
+        .concrete_constraints
+        .iter()
+        .chain(&tree.var_constraints)
+        .chain(&tree.bv_constraints)
+    {
+        for v in c.type_vars() {
+            labels.entry(v).or_default();
+        }
+        match c {
+            TypeExpr::Concrete(v, ty) => labels.entry(*v).or_default().push(type_to_num(ty)),
+            TypeExpr::Value(v, n) => labels.entry(*v).or_default().push(n.to_string()),
+            TypeExpr::Variable(u, v) => edges.push(format!("t{} -> t{} [dir=none]", u, v)),
+            TypeExpr::WidthInt(..)
+            | TypeExpr::ConditionalWidthInt(..)
+            | TypeExpr::WidthAtMost(..)
+            | TypeExpr::Symbolic(..) => {
+                let vars = c.type_vars();
+                let label = escape(&c.to_string());
+                for v in &vars[1..] {
+                    edges.push(format!(
+                        "t{} -> t{} [style=dashed, label=\"{}\"]",
+                        vars[0], v, label
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut out = format!("digraph \"{}\" {{\n", escape(rule));
+    for (v, tys) in labels.into_iter().sorted_by_key(|(v, _)| *v) {
+        let mut label = vec![format!("t{}", v)];
+        label.extend(names.get(&v).cloned());
+        label.extend(tys.into_iter().sorted().dedup());
+        out.push_str(&format!(
+            "  t{} [label=\"{}\"];\n",
+            v,
+            label.iter().map(|l| escape(l)).join("\\n")
+        ));
+    }
+    for edge in edges.into_iter().sorted() {
+        out.push_str(&format!("  {};\n", edge));
+    }
+    out.push_str("}\n");
+    out
+}
+
+// The rule's constraints grouped by kind and sorted by type variable, each
+// followed by the names of the type variables it mentions.
+fn dump_constraints(rule: &str, tree: &RuleParseTree, names: &HashMap<u32, String>) -> String {
+    let mut out = format!("Constraints for rule `{}`:\n", rule);
+    for (kind, constraints) in [
+        ("concrete", &tree.concrete_constraints),
+        ("variable", &tree.var_constraints),
+        ("bitvector", &tree.bv_constraints),
+    ] {
+        out.push_str(&format!("  {}:\n", kind));
+        let sorted = constraints
+            .iter()
+            .map(|c| (c.type_vars(), c.to_string()))
+            .sorted();
+        for (vars, constraint) in sorted {
+            let legend = vars
+                .iter()
+                .unique()
+                .filter_map(|v| names.get(v).map(|n| format!("t{} = {}", v, n)))
+                .join(", ");
+            out.push_str(&format!("    {}    ; {}\n", constraint, legend));
+        }
+    }
+    out
+}
+
+// Check the solved types of the concrete test's term, where it appears in the
+// LHS, against the types of the test's inputs and output.
+fn check_concrete_test(
+    test: &ConcreteTest,
+    lhs: &TypeVarNode,
+    solution: &HashMap<u32, annotation_ir::Type>,
+) -> Result<(), String> {
+    fn find_term<'a>(node: &'a TypeVarNode, prefix: &str) -> Option<&'a TypeVarNode> {
+        if node.ident.starts_with(prefix) {
+            return Some(node);
+        }
+        node.children.iter().find_map(|c| find_term(c, prefix))
+    }
+    // Rules without the test's term aren't covered by the test
+    let node = match find_term(lhs, &format!("{}__", escape_name(&test.termname))) {
+        Some(node) => node,
+        None => return Ok(()),
+    };
+    if node.children.len() != test.args.len() {
+        return Err(format!(
+            "`{}` has {} arguments but the test gives {}",
+            test.termname,
+            node.children.len(),
+            test.args.len()
+        ));
+    }
+
+    let expected = node
+        .children
+        .iter()
+        .zip(&test.args)
+        .map(|(child, input)| (child.type_var, input))
+        .chain(std::iter::once((node.type_var, &test.output)));
+    for (type_var, input) in expected {
+        let ty = annotation_type_for_vir_type(&input.ty);
+        match solution.get(&type_var) {
+            Some(inferred) if *inferred == ty => {}
+            inferred => {
+                return Err(format!(
+                    "`{}` has type {} but `{}` was inferred as {}",
+                    input.literal,
+                    type_to_num(&ty),
+                    test.termname,
+                    inferred.map_or("nothing".to_string(), type_to_num)
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+// Recursive process tree
+fn create_parse_tree_pattern(
+    rule: &sema::Rule,
+    pattern: &sema::Pattern,
+    tree: &mut RuleParseTree,
+    typeenv: &TypeEnv,
+    termenv: &TermEnv,
+    term: &String,
+    types: &TermSignature,
+) -> TypeVarNode {
+    match pattern {
+        sema::Pattern::Term(_, term_id, args) => {
+            let sym = termenv.terms[term_id.index()].name;
+            let name = typeenv.syms[sym.index()].clone();
+
+            // process children first
+            let mut children = vec![];
+            for (i, arg) in args.iter().enumerate() {
+                let child =
+                    create_parse_tree_pattern(rule, arg, tree, typeenv, termenv, term, types);
+
+                // Our specified input term, use external types
+                if name.eq(term) {
+                    tree.concrete_constraints.insert(TypeExpr::Concrete(
+                        child.type_var,
+                        annotation_type_for_vir_type(&types.args[i]),
+                    ));
+                }
+                children.push(child);
+            }
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            if name.eq(term) {
+                tree.concrete_constraints.insert(TypeExpr::Concrete(
+                    type_var,
+                    annotation_type_for_vir_type(&types.ret),
+                ));
+            }
+
+            TypeVarNode {
+                ident: format!("{}__{}", escape_name(&name), type_var),
+                construct: TypeVarConstruct::Term(term_id.clone()),
+                type_var,
+                children,
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::Var(_, var_id) => {
+            let sym = rule.vars[var_id.index()].name;
+            let ident = typeenv.syms[sym.index()].clone();
+
+            let type_var = tree
+                .varid_to_type_var_map
+                .entry(*var_id)
+                .or_insert(tree.next_type_var);
+            if *type_var == tree.next_type_var {
+                tree.next_type_var += 1;
+            }
+            let ident = format!(
+                "{}__clif{}__{}",
+                escape_name(&ident),
+                var_id.index(),
+                *type_var
+            );
+            // this is a base case so there are no children
+            TypeVarNode {
+                ident,
+                construct: TypeVarConstruct::Var,
+                type_var: *type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::BindPattern(_, var_id, subpat) => {
+            let sym = rule.vars[var_id.index()].name;
+
+            let type_var = *tree
+                .varid_to_type_var_map
+                .entry(*var_id)
+                .or_insert(tree.next_type_var);
+            if type_var == tree.next_type_var {
+                tree.next_type_var += 1;
+            }
+
+            let ident = format!(
+                "{}__clif{}__{}",
+                escape_name(&typeenv.syms[sym.index()]),
+                var_id.index(),
+                type_var
+            );
+
+            // this is a base case so there are no children
+            let var_node = TypeVarNode {
+                ident: ident.clone(),
+                construct: TypeVarConstruct::Var,
+                type_var: type_var,
+                children: vec![],
+                assertions: vec![],
+            };
+
+            let subpat_node =
+                create_parse_tree_pattern(rule, subpat, tree, typeenv, termenv, term, types);
+
+            let bind_type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.var_constraints
+                .insert(TypeExpr::Variable(type_var, subpat_node.type_var));
+            tree.var_constraints
+                .insert(TypeExpr::Variable(bind_type_var, type_var));
+            tree.var_constraints
+                .insert(TypeExpr::Variable(bind_type_var, subpat_node.type_var));
+            tree.bind_patterns.push((
+                typeenv.syms[sym.index()].clone(),
+                type_var,
+                subpat_node.type_var,
+                bind_type_var,
+            ));
+
+            TypeVarNode {
+                ident,
+                construct: TypeVarConstruct::BindPattern,
+                type_var: type_var,
+                children: vec![var_node, subpat_node],
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::Wildcard(_) => {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+            TypeVarNode {
+                ident: format!("wildcard__{}", type_var),
+                construct: TypeVarConstruct::Wildcard(type_var),
+                type_var: type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::ConstPrim(ty, sym) => {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+            let name = typeenv.syms[sym.index()].clone();
+            let construct = const_prim_construct(&name, *ty);
+            let name = format!("{}__{}", escape_name(&name), type_var);
+
+            TypeVarNode {
+                ident: name,
+                construct,
+                type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::ConstInt(_, num) => {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+            let name = format!("{}__{}", num, type_var);
+            TypeVarNode {
+                ident: name,
+                construct: TypeVarConstruct::Const(*num),
+                type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Pattern::And(_, subpats) => {
+            let mut children = vec![];
+            let mut ty_vars = vec![];
+            for p in subpats {
+                let child = create_parse_tree_pattern(rule, p, tree, typeenv, termenv, term, types);
+                ty_vars.push(child.type_var);
+                children.push(child);
+            }
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            // Assert all sub type constraints are equivalent to the first subexpression
+            let first = ty_vars[0];
+            for e in &ty_vars[1..] {
+                tree.var_constraints
+                    .insert(TypeExpr::Variable(first, e.to_owned()));
+            }
+
+            TypeVarNode {
+                ident: String::from("and"),
+                construct: TypeVarConstruct::And,
+                type_var,
+                children,
+                assertions: vec![],
+            }
+        }
+    }
+}
+
+fn create_parse_tree_expr(
+    rule: &sema::Rule,
+    expr: &sema::Expr,
+    tree: &mut RuleParseTree,
+    typeenv: &TypeEnv,
+    termenv: &TermEnv,
+) -> Result<TypeVarNode, TypeError> {
+    let node = match expr {
+        sema::Expr::Term(_, term_id, args) => {
+            let sym = termenv.terms[term_id.index()].name;
+            let name = typeenv.syms[sym.index()].clone();
+
+            // process children first
+            let mut children = vec![];
+            for arg in args {
+                let child = create_parse_tree_expr(rule, arg, tree, typeenv, termenv)?;
+                children.push(child);
+            }
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            TypeVarNode {
+                ident: format!("{}__{}", escape_name(&name), type_var),
+                construct: TypeVarConstruct::Term(term_id.clone()),
+                type_var,
+                children,
+                assertions: vec![],
+            }
+        }
+        sema::Expr::Var(_, var_id) => {
+            if var_id.index() >= rule.vars.len() {
+                return Err(TypeError::UnboundVar {
+                    rule: rule_name(rule, typeenv),
+                    var: var_id.index(),
+                });
+            }
+            let sym = rule.vars[var_id.index()].name;
+            let ident = typeenv.syms[sym.index()].clone();
+
+            let type_var = tree
+                .varid_to_type_var_map
+                .entry(*var_id)
+                .or_insert(tree.next_type_var);
+            if *type_var == tree.next_type_var {
+                tree.next_type_var += 1;
+            }
+            let ident = format!(
+                "{}__clif{}__{}",
+                escape_name(&ident),
+                var_id.index(),
+                *type_var
+            );
+            // this is a base case so there are no children
+            TypeVarNode {
+                ident,
+                construct: TypeVarConstruct::Var,
+                type_var: *type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Expr::ConstPrim(ty, sym) => {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+            let name = typeenv.syms[sym.index()].clone();
+            let construct = const_prim_construct(&name, *ty);
+            let name = format!("{}__{}", escape_name(&name), type_var);
+            TypeVarNode {
+                ident: name,
+                construct,
+                type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Expr::ConstInt(_, num) => {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+            let name = format!("{}__{}", num, type_var);
+            TypeVarNode {
+                ident: name,
+                construct: TypeVarConstruct::Const(*num),
+                type_var,
+                children: vec![],
+                assertions: vec![],
+            }
+        }
+        sema::Expr::Let { bindings, body, .. } => {
+            let mut children = vec![];
+            let mut bound = vec![];
+            for (i, (varid, declared, expr)) in bindings.iter().enumerate() {
+                let sym = rule.vars[varid.index()].name;
+                let var = typeenv.syms[sym.index()].clone();
+                // A binding may only use outer variables and earlier bindings
+                if let Some((later, _, _)) = bindings[i..]
+                    .iter()
+                    .find(|(later, _, _)| expr_uses_var(expr, *later))
+                {
+                    let later_sym = rule.vars[later.index()].name;
+                    return Err(TypeError::CyclicLet {
+                        rule: rule_name(rule, typeenv),
+                        var,
+                        uses: typeenv.syms[later_sym.index()].clone(),
+                    });
+                }
+                let subpat_node = create_parse_tree_expr(rule, expr, tree, typeenv, termenv)?;
+
+                let ty_var = tree.next_type_var;
+                tree.next_type_var += 1;
+
+                tree.var_constraints
+                    .insert(TypeExpr::Variable(ty_var, subpat_node.type_var));
+
+                tree.varid_to_type_var_map.insert(*varid, ty_var);
+                children.push(subpat_node);
+                let ident = format!("{}__clif{}__{}", escape_name(&var), varid.index(), ty_var);
+                tree.quantified_vars.insert(ident.clone(), ty_var);
+                bound.push((ident, *declared));
+            }
+            let body = create_parse_tree_expr(rule, body, tree, typeenv, termenv)?;
+            let body_var = body.type_var;
+            children.push(body);
+
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            let name = format!("let__{}", type_var);
+
+            // The let should have the same type as the body
+            tree.var_constraints
+                .insert(TypeExpr::Variable(type_var, body_var));
+
+            TypeVarNode {
+                ident: name,
+                construct: TypeVarConstruct::Let(bound),
+                type_var,
+                children,
+                assertions: vec![],
+            }
+        }
+    };
+    Ok(node)
+}
+
+// Whether an expression uses the variable. Every binding in a rule has its
+// own `VarId`, so a use can't refer to a different binding of the same name.
+fn expr_uses_var(expr: &sema::Expr, var: VarId) -> bool {
+    match expr {
+        sema::Expr::Term(_, _, args) => args.iter().any(|a| expr_uses_var(a, var)),
+        sema::Expr::Var(_, v) => *v == var,
+        sema::Expr::ConstPrim(..) | sema::Expr::ConstInt(..) => false,
+        sema::Expr::Let { bindings, body, .. } => {
+            bindings.iter().any(|(_, _, e)| expr_uses_var(e, var)) || expr_uses_var(body, var)
+        }
+    }
+}
+
+// Check each bind pattern before solving, so its conflict is reported by
+// name rather than as an unsat core. Leaving out the pattern's own
+// equalities, the type the variable is constrained to must be compatible with
+// the one its subpattern is constrained to, as the unifier would merge them.
+fn check_bind_patterns(rule: &str, tree: &RuleParseTree) -> Result<(), TypeError> {
+    for (var, bound, pattern, bind) in &tree.bind_patterns {
+        let own = [
+            TypeExpr::Variable(*bound, *pattern),
+            TypeExpr::Variable(*bind, *bound),
+            TypeExpr::Variable(*bind, *pattern),
+        ];
+        let mut unifier = Unifier::default();
+        let mut consistent = true;
+        for c in tree.var_constraints.iter().filter(|c| !own.contains(c)) {
+            if let TypeExpr::Variable(u, v) = c {
+                consistent &= unifier.union(*u, *v, c).is_ok();
+            }
+        }
+        for c in &tree.concrete_constraints {
+            if let TypeExpr::Concrete(u, ty) = c {
+                consistent &= unifier.assign(*u, ty, c).is_ok();
+            }
+        }
+        // A conflict that doesn't need the pattern is left to the solver
+        if !consistent {
+            continue;
+        }
+        let bound_rep = unifier.find(*bound);
+        let pattern_rep = unifier.find(*pattern);
+        if let (Some(bound_ty), Some(pattern_ty)) = (
+            unifier.class_type(bound_rep),
+            unifier.class_type(pattern_rep),
+        ) {
+            if Unifier::meet(&bound_ty, &pattern_ty).is_none() {
+                return Err(TypeError::BindMismatch {
+                    rule: rule.to_string(),
+                    var: var.clone(),
+                    bound: bound_ty,
+                    pattern: pattern_ty,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Name the let binding whose declared type is part of an unsat core. A core
+// that reaches the conflict some other way, e.g. through the return type of
+// the term giving the value, isn't the declaration's fault.
+fn blame_let_decl(err: TypeError, let_decls: &[(String, TypeExpr)]) -> TypeError {
+    if let TypeError::Unsat { rule, core, .. } = &err {
+        let declared = let_decls.iter().find(|(_, decl)| core.contains(decl));
+        if let Some((var, _)) = declared {
+            return TypeError::LetTypeMismatch {
+                rule: rule.clone(),
+                var: var.clone(),
+                core: core.clone(),
+            };
+        }
+    }
+    err
+}
+
+// The amount of a bitvector shift or rotate has the width of the value it
+// moves, or with narrow shift amounts allowed, at most that width.
+fn add_shift_amount_constraint(tree: &mut RuleParseTree, value: u32, amount: u32) {
+    if tree.narrow_shift_amounts {
+        tree.concrete_constraints
+            .insert(TypeExpr::WidthAtMost(amount, value));
+    } else {
+        tree.var_constraints
+            .insert(TypeExpr::Variable(value, amount));
+    }
+}
+
+// Reject the first `conv_to` whose solved result is narrower than its solved
+// operand. Operands of unknown width are let through.
+fn check_conv_tos(
+    rule: &str,
+    conv_tos: &[(String, u32, u32)],
+    solution: &HashMap<u32, annotation_ir::Type>,
+) -> Result<(), TypeError> {
+    for (term, operand, result) in conv_tos {
+        if let (
+            Some(annotation_ir::Type::BitVectorWithWidth(from)),
+            Some(annotation_ir::Type::BitVectorWithWidth(to)),
+        ) = (solution.get(operand), solution.get(result))
+        {
+            if to < from {
+                return Err(TypeError::NarrowingConvTo {
+                    rule: rule.to_string(),
+                    term: term.clone(),
+                    from: *from,
+                    to: *to,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// How many of a switch's unmatched values an error lists.
+const MAX_MISSING_SWITCH_VALUES: usize = 8;
+
+// Reject the first switch on a bitvector at most `max_width` wide whose
+// constant matches miss a value. Switches with a non-constant match are let
+// through, since what they cover isn't known.
+fn check_switches(
+    rule: &str,
+    switches: &[(String, u32, Option<Vec<i128>>)],
+    max_width: usize,
+    solution: &HashMap<u32, annotation_ir::Type>,
+) -> Result<(), TypeError> {
+    for (term, control, values) in switches {
+        if let (Some(values), Some(annotation_ir::Type::BitVectorWithWidth(width))) =
+            (values, solution.get(control))
+        {
+            if *width > max_width {
+                continue;
+            }
+            // Count the values covered rather than enumerating the whole
+            // range, which is too large to walk for wide switches.
+            let size = u32::try_from(*width)
+                .ok()
+                .and_then(|w| 1i128.checked_shl(w))
+                .filter(|size| *size > 0);
+            let complete = size.is_some_and(|size| {
+                values
+                    .iter()
+                    .filter(|v| (0..size).contains(*v))
+                    .unique()
+                    .count() as i128
+                    == size
+            });
+            if !complete {
+                let missing: Vec<i128> = (0..size.unwrap_or(i128::MAX))
+                    .filter(|v| !values.contains(v))
+                    .take(MAX_MISSING_SWITCH_VALUES)
+                    .collect();
+                return Err(TypeError::IncompleteSwitch {
+                    rule: rule.to_string(),
+                    term: term.clone(),
+                    width: *width,
+                    missing,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// The primitive constants with a built-in meaning. Any other constant is
+// typed by its declared type instead.
+fn const_prim_construct(name: &str, ty: TypeId) -> TypeVarConstruct {
+    match name {
+        "I128" => TypeVarConstruct::Const(128),
+        "I64" => TypeVarConstruct::Const(64),
+        "I32" => TypeVarConstruct::Const(32),
+        "I16" => TypeVarConstruct::Const(16),
+        "I8" => TypeVarConstruct::Const(8),
+        "true" => TypeVarConstruct::BoolConst(true),
+        "false" => TypeVarConstruct::BoolConst(false),
+        _ => TypeVarConstruct::OpaqueConst(ty),
+    }
+}
+
+// The value of an integer expression built from constants, arithmetic, and
+// the widths of bitvectors whose widths are already fixed.
+fn const_fold_to_int(e: &veri_ir::Expr, tree: &RuleParseTree) -> Option<i128> {
+    match e {
+        Expr::Terminal(veri_ir::Terminal::Const(c, _)) => Some(*c),
+        Expr::Binary(op, x, y) => {
+            let x = const_fold_to_int(x, tree)?;
+            let y = const_fold_to_int(y, tree)?;
+            match op {
+                veri_ir::BinaryOp::BVAdd => x.checked_add(y),
+                veri_ir::BinaryOp::BVSub => x.checked_sub(y),
+                veri_ir::BinaryOp::BVMul => x.checked_mul(y),
+                _ => None,
+            }
+        }
+        Expr::WidthOf(x) => {
+            let t = tree.ty_vars.get(x)?;
+            tree.concrete_constraints.iter().find_map(|c| match c {
+                TypeExpr::Concrete(v, annotation_ir::Type::BitVectorWithWidth(w)) if v == t => {
+                    Some(*w as i128)
+                }
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn add_annotation_constraints(
+    expr: annotation_ir::Expr,
+    tree: &mut RuleParseTree,
+    annotation_info: &mut AnnotationTypeInfo,
+) -> Result<(veri_ir::Expr, u32), TypeError> {
+    let (e, t) = match expr {
+        annotation_ir::Expr::Var(x, ..) => {
+            let mut t = tree.next_type_var;
+            if annotation_info.var_to_type_var.contains_key(&x) {
+                t = annotation_info.var_to_type_var[&x];
+            } else {
+                annotation_info.var_to_type_var.insert(x.clone(), t);
+                tree.next_type_var += 1;
+            }
+            let name = format!(
+                "{}__{}__{}",
+                escape_name(&annotation_info.term),
+                escape_name(&x),
+                t
+            );
+
+            // Support the introduction of extra variables in the specification.
+            //
+            // TODO(mbm): understand whether this needs to be in quantified, free or both?
+            tree.quantified_vars.insert(name.clone(), t);
+            tree.free_vars.insert(name.clone(), t);
+
+            (veri_ir::Expr::Terminal(veri_ir::Terminal::Var(name)), t)
+        }
+        annotation_ir::Expr::Const(c, ..) => {
+            let t = tree.next_type_var;
+            let e = veri_ir::Expr::Terminal(veri_ir::Terminal::Const(c.value, t));
+            match c.ty {
+                annotation_ir::Type::BitVector => {
+                    let ty = annotation_ir::Type::BitVectorWithWidth(c.width);
+                    tree.concrete_constraints.insert(TypeExpr::Concrete(t, ty));
+                }
+                _ => {
+                    tree.concrete_constraints
+                        .insert(TypeExpr::Concrete(t, c.ty.clone()));
+                }
+            }
+            tree.next_type_var += 1;
+
+            // If constant is known, add the value to the tree. Useful for
+            // capturing isleTypes
+            tree.type_var_to_val_map.insert(t, c.value);
+            (e, t)
+        }
+        annotation_ir::Expr::True => {
+            let t = tree.next_type_var;
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::Terminal(veri_ir::Terminal::True), t)
+        }
+        annotation_ir::Expr::False => {
+            let t = tree.next_type_var;
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::Terminal(veri_ir::Terminal::False), t)
+        }
+
+        annotation_ir::Expr::WidthOf(x) => {
+            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+            // The operand may be an integer or a not-yet-resolved poly value,
+            // so its width is only linked once it's known to be a bitvector.
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Int));
+            tree.concrete_constraints
+                .insert(TypeExpr::ConditionalWidthInt(tx, t));
+            (veri_ir::Expr::WidthOf(Box::new(ex)), t)
+        }
+
+        annotation_ir::Expr::Eq(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::Eq, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+        annotation_ir::Expr::Imp(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::Imp, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+        annotation_ir::Expr::Lte(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::Lte, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+
+        annotation_ir::Expr::Not(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::Unary(veri_ir::UnaryOp::Not, Box::new(e1)), t)
+        }
+        annotation_ir::Expr::Or(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::Or, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+        annotation_ir::Expr::And(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::And, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+
+        annotation_ir::Expr::BVSgt(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVSgt, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVSgte(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVSgte, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVSlt(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVSlt, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVSlte(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVSlte, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVUgt(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVUgt, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVUgte(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVUgte, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVUlt(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVUlt, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVUlte(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVUlte, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVSaddo(x, y) => {
+            add_same_width_comparison(veri_ir::BinaryOp::BVSaddo, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVNeg(x) => {
+            add_same_width_unary(veri_ir::UnaryOp::BVNeg, *x, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVNot(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Unary(veri_ir::UnaryOp::BVNot, Box::new(e1)),
+                t,
+            )
+        }
+
+        annotation_ir::Expr::BVMul(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVMul, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVUDiv(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVUDiv, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVSDiv(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVSDiv, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVAdd(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVAdd, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVSub(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVSub, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVUrem(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVUrem, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVSrem(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVSrem, *x, *y, tree, annotation_info)?
+        }
+
+        annotation_ir::Expr::BVAnd(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVAnd, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVOr(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVOr, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVXor(x, y) => {
+            add_same_width_binary(veri_ir::BinaryOp::BVXor, *x, *y, tree, annotation_info)?
+        }
+        annotation_ir::Expr::BVRotl(x, a) => {
+            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
+            add_shift_amount_constraint(tree, xt, at);
+
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVRotl, Box::new(xe), Box::new(ae)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVRotr(x, a) => {
+            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
+            add_shift_amount_constraint(tree, xt, at);
+
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVRotr, Box::new(xe), Box::new(ae)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVShl(x, a) => {
+            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
+            add_shift_amount_constraint(tree, xt, at);
+
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVShl, Box::new(xe), Box::new(ae)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVShr(x, a) => {
+            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
+            add_shift_amount_constraint(tree, xt, at);
+
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVShr, Box::new(xe), Box::new(ae)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVAShr(x, a) => {
+            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
+            add_shift_amount_constraint(tree, xt, at);
+
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVAShr, Box::new(xe), Box::new(ae)),
+                t,
+            )
+        }
+        annotation_ir::Expr::Lt(x, y) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Binary(veri_ir::BinaryOp::Lt, Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+
+        annotation_ir::Expr::BVConvTo(w, x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            let width = match *w {
+                annotation_ir::Width::Const(x) => x,
+                annotation_ir::Width::RegWidth => tree.target.reg_width,
+            };
+
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(width),
+            ));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.conv_tos.push((annotation_info.term.clone(), t1, t));
+
+            (veri_ir::Expr::BVConvTo(Box::new(e1)), t)
+        }
+        annotation_ir::Expr::BVConvToVarWidth(w, x) => {
+            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            // In the dynamic case, we don't know the width at this point
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
+
+            if let Some(w) = const_fold_to_int(&we, tree) {
+                let width = usize::try_from(w).ok().filter(|w| *w > 0).ok_or_else(|| {
+                    TypeError::InvalidAnnotation {
+                        term: annotation_info.term.clone(),
+                        message: format!("conv_to width {} is not a valid bitvector width", w),
+                    }
+                })?;
+                tree.concrete_constraints.insert(TypeExpr::Concrete(
+                    t,
+                    annotation_ir::Type::BitVectorWithWidth(width),
+                ));
+                tree.bv_constraints
+                    .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+
+                (veri_ir::Expr::BVConvTo(Box::new(e1)), t)
+            } else {
+                tree.concrete_constraints.insert(TypeExpr::WidthInt(t, wt));
+                tree.bv_constraints
+                    .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+                tree.bv_constraints
+                    .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+
+                (
+                    veri_ir::Expr::BVConvToVarWidth(Box::new(we), Box::new(e1)),
+                    t,
+                )
+            }
+        }
+        annotation_ir::Expr::BVSignExtToVarWidth(w, x) => {
+            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            // In the dynamic case, we don't know the width at this point
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+
+            (
+                veri_ir::Expr::BVSignExtToVarWidth(Box::new(we), Box::new(e1)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVZeroExtTo(w, x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            let width = match *w {
+                veri_ir::annotation_ir::Width::Const(c) => c,
+                veri_ir::annotation_ir::Width::RegWidth => tree.target.reg_width,
+            };
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(width),
+            ));
+
+            (veri_ir::Expr::BVZeroExtTo(width, Box::new(e1)), t)
+        }
+        annotation_ir::Expr::BVZeroExtToVarWidth(w, x) => {
+            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            // In the dynamic case, we don't know the width at this point
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+
+            (
+                veri_ir::Expr::BVZeroExtToVarWidth(Box::new(we), Box::new(e1)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVSignExtTo(w, x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            let width = match *w {
+                veri_ir::annotation_ir::Width::Const(c) => c,
+                veri_ir::annotation_ir::Width::RegWidth => tree.target.reg_width,
+            };
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(width),
+            ));
+
+            tree.next_type_var += 1;
+
+            (veri_ir::Expr::BVSignExtTo(width, Box::new(e1)), t)
+        }
+        annotation_ir::Expr::BVExtract(l, r, x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            if r > l {
+                return Err(TypeError::InvalidAnnotation {
+                    term: annotation_info.term.clone(),
+                    message: format!("extract {} {} has its bounds swapped", l, r),
+                });
+            }
+            // If the operand's width is already fixed, the extract must fit in it.
+            let operand_width = tree.concrete_constraints.iter().find_map(|c| match c {
+                TypeExpr::Concrete(v, annotation_ir::Type::BitVectorWithWidth(w)) if *v == t1 => {
+                    Some(*w)
+                }
+                _ => None,
+            });
+            if let Some(w) = operand_width {
+                if l >= w {
+                    return Err(TypeError::InvalidAnnotation {
+                        term: annotation_info.term.clone(),
+                        message: format!("extract {} {} is out of bounds for bv{}", l, r, w),
+                    });
+                }
+            }
+            let t = tree.next_type_var;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(l - r + 1),
+            ));
+
+            tree.next_type_var += 1;
+
+            (veri_ir::Expr::BVExtract(l, r, Box::new(e1)), t)
+        }
+        annotation_ir::Expr::BVConcat(xs) => {
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            let mut sum_bvs = vec![];
+
+            let mut exprs = vec![];
+            for (i, x) in xs.into_iter().enumerate() {
+                let (xe, xt) = add_annotation_constraints(x, tree, annotation_info)?;
+                // An integer, e.g. an unsuffixed literal, has no width to add
+                if tree
+                    .concrete_constraints
+                    .contains(&TypeExpr::Concrete(xt, annotation_ir::Type::Int))
+                {
+                    return Err(TypeError::InvalidAnnotation {
+                        term: annotation_info.term.clone(),
+                        message: format!(
+                            "concat operand {} is an integer; give it a width with int2bv",
+                            i
+                        ),
+                    });
+                }
+                tree.bv_constraints
+                    .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+
+                // add each bv to the sum_bv
+                sum_bvs.push(xt);
+
+                exprs.push(xe);
+            }
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+            tree.concrete_constraints
+                .insert(TypeExpr::symbolic(sum_bvs, vec![t]));
+            tree.next_type_var += 1;
+
+            (veri_ir::Expr::BVConcat(exprs), t)
+        }
+        annotation_ir::Expr::BVIntToBv(w, x) => {
+            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(tx, annotation_ir::Type::Int));
+
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(w),
+            ));
+
+            (veri_ir::Expr::BVIntToBV(w, Box::new(ex)), t)
+        }
+        annotation_ir::Expr::BVToInt(x) => {
+            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(tx, annotation_ir::Type::BitVector));
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Int));
+
+            (veri_ir::Expr::BVToInt(Box::new(ex)), t)
+        }
+        annotation_ir::Expr::Conditional(c, t, e) => {
+            let (e1, t1) = add_annotation_constraints(*c, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*t, tree, annotation_info)?;
+            let (e3, t3) = add_annotation_constraints(*e, tree, annotation_info)?;
+            let t = tree.next_type_var;
+
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
+            tree.var_constraints.insert(TypeExpr::Variable(t2, t3));
+            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::Conditional(Box::new(e1), Box::new(e2), Box::new(e3)),
+                t,
+            )
+        }
+        annotation_ir::Expr::Switch(c, cases) => {
+            let (c_expr, c_t) = add_annotation_constraints(*c, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            let values = cases
+                .iter()
+                .map(|(m, _)| match m {
+                    annotation_ir::Expr::Const(c, ..) => Some(c.value),
+                    _ => None,
+                })
+                .collect();
+            tree.switches
+                .push((annotation_info.term.clone(), c_t, values));
+
+            let mut case_exprs = vec![];
+            for (m, b) in cases {
+                let (case_expr, case_t) =
+                    add_annotation_constraints(m.clone(), tree, annotation_info)?;
+                let (body_expr, body_t) =
+                    add_annotation_constraints(b.clone(), tree, annotation_info)?;
+
+                tree.var_constraints.insert(TypeExpr::Variable(c_t, case_t));
+                tree.var_constraints.insert(TypeExpr::Variable(t, body_t));
+                case_exprs.push((case_expr, body_expr));
+            }
+            (veri_ir::Expr::Switch(Box::new(c_expr), case_exprs), t)
+        }
+        annotation_ir::Expr::CLZ(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::CLZ(Box::new(e1)), t)
+        }
+        annotation_ir::Expr::A64CLZ(ty, x) => {
+            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(tree.target.reg_width),
+            ));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::A64CLZ(Box::new(e0), Box::new(e1)), t)
+        }
+        annotation_ir::Expr::CLS(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::CLS(Box::new(e1)), t)
+        }
+        annotation_ir::Expr::A64CLS(ty, x) => {
+            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(tree.target.reg_width),
+            ));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::A64CLS(Box::new(e0), Box::new(e1)), t)
+        }
+        annotation_ir::Expr::Rev(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::Rev(Box::new(e1)), t)
+        }
+        annotation_ir::Expr::A64Rev(ty, x) => {
+            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(tree.target.reg_width),
+            ));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::A64Rev(Box::new(e0), Box::new(e1)), t)
+        }
+        annotation_ir::Expr::BVSubs(ty, x, y) => {
+            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info)?;
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+
+            // For aarch64, subs sets 4 flags. Model these as 4 bit appended to the left of the
+            // register; `TargetConfig::subs_flags_bits` gives where they are.
+            tree.concrete_constraints.insert(TypeExpr::Concrete(
+                t,
+                annotation_ir::Type::BitVectorWithWidth(tree.target.subs_width()),
+            ));
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+
+            tree.next_type_var += 1;
+            (
+                veri_ir::Expr::BVSubs(Box::new(e0), Box::new(e1), Box::new(e2)),
+                t,
+            )
+        }
+        annotation_ir::Expr::BVPopcnt(x) => {
+            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info)?;
+
+            let t = tree.next_type_var;
+
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+            tree.bv_constraints
+                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+            tree.next_type_var += 1;
+            (veri_ir::Expr::BVPopcnt(Box::new(e1)), t)
+        }
+    };
+    tree.ty_vars.insert(e.clone(), t);
+    Ok((e, t))
+}
+
+// A binary bitvector op whose operands and result all have the same width.
+fn add_same_width_binary(
+    op: veri_ir::BinaryOp,
+    x: annotation_ir::Expr,
+    y: annotation_ir::Expr,
+    tree: &mut RuleParseTree,
+    annotation_info: &mut AnnotationTypeInfo,
+) -> Result<(veri_ir::Expr, u32), TypeError> {
+    let (e1, t1) = add_annotation_constraints(x, tree, annotation_info)?;
+    let (e2, t2) = add_annotation_constraints(y, tree, annotation_info)?;
+    let t = tree.next_type_var;
+
+    tree.bv_constraints
+        .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+    tree.bv_constraints
+        .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    tree.bv_constraints
+        .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
+    tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+    tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+    tree.var_constraints.insert(TypeExpr::Variable(t, t2));
+
+    tree.next_type_var += 1;
+    Ok((veri_ir::Expr::Binary(op, Box::new(e1), Box::new(e2)), t))
+}
+
+// A binary predicate over two operands of the same type.
+fn add_same_width_comparison(
+    op: veri_ir::BinaryOp,
+    x: annotation_ir::Expr,
+    y: annotation_ir::Expr,
+    tree: &mut RuleParseTree,
+    annotation_info: &mut AnnotationTypeInfo,
+) -> Result<(veri_ir::Expr, u32), TypeError> {
+    let (e1, t1) = add_annotation_constraints(x, tree, annotation_info)?;
+    let (e2, t2) = add_annotation_constraints(y, tree, annotation_info)?;
+    let t = tree.next_type_var;
+
+    tree.concrete_constraints
+        .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
+    tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
+
+    tree.next_type_var += 1;
+    Ok((veri_ir::Expr::Binary(op, Box::new(e1), Box::new(e2)), t))
+}
+
+// A unary bitvector op whose result has the width of its operand.
+fn add_same_width_unary(
+    op: veri_ir::UnaryOp,
+    x: annotation_ir::Expr,
+    tree: &mut RuleParseTree,
+    annotation_info: &mut AnnotationTypeInfo,
+) -> Result<(veri_ir::Expr, u32), TypeError> {
+    let (e1, t1) = add_annotation_constraints(x, tree, annotation_info)?;
+
+    let t = tree.next_type_var;
+    tree.bv_constraints
+        .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
+    tree.bv_constraints
+        .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+
+    tree.next_type_var += 1;
+    Ok((veri_ir::Expr::Unary(op, Box::new(e1)), t))
+}
+
+fn add_isle_constraints(
+    term: &sema::Term,
+    tree: &mut RuleParseTree,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    annotation_info: &mut AnnotationTypeInfo,
+    annotation: annotation_ir::TermSignature,
+) {
+    let mut annotation_vars = vec![];
+    for a in annotation.args {
+        annotation_vars.push(a.name);
+    }
+    annotation_vars.push(annotation.ret.name);
+
+    let mut isle_types = vec![];
+    for arg_ty in term.arg_tys.iter() {
+        isle_types.push(arg_ty.clone());
+    }
+    isle_types.push(term.ret_ty.clone());
+    assert_eq!(annotation_vars.len(), isle_types.len());
+
+    for (isle_type_id, annotation_var) in isle_types.iter().zip(annotation_vars) {
+        // in case the var was not in the annotation
+        if !annotation_info
+            .var_to_type_var
+            .contains_key(&annotation_var)
+        {
+            let type_var = tree.next_type_var;
+            tree.next_type_var += 1;
+
+            annotation_info
+                .var_to_type_var
+                .insert(annotation_var.clone(), type_var);
+        }
+
+        let type_var = annotation_info.var_to_type_var[&annotation_var];
+        if let Some(ir_type) = annotation_env.model_map.get(isle_type_id) {
+            match ir_type {
+                annotation_ir::Type::BitVector => tree
+                    .bv_constraints
+                    .insert(TypeExpr::Concrete(type_var, ir_type.clone())),
+                _ => tree
+                    .concrete_constraints
+                    .insert(TypeExpr::Concrete(type_var, ir_type.clone())),
+            };
+        } else if typeenv.types[isle_type_id.index()].name(typeenv) == "Unit" {
+            // Side-effecting terms return `Unit`, which has no model in the IR
+            tree.unit_vars.insert(type_var);
+        }
+    }
+}
+
+/// Whether a term takes a single argument of its return type, and so can be
+/// assumed to be the identity when it has no annotation.
+fn is_pass_through(term: &sema::Term) -> bool {
+    term.arg_tys.len() == 1 && term.arg_tys[0] == term.ret_ty
+}
+
+fn add_rule_constraints(
+    tree: &mut RuleParseTree,
+    curr: &mut TypeVarNode,
+    termenv: &TermEnv,
+    typeenv: &TypeEnv,
+    annotation_env: &AnnotationEnv,
+    annotation_infos: &mut Vec<AnnotationTypeInfo>,
+    config: &Config,
+    rhs: bool,
+) -> Result<veri_ir::Expr, TypeError> {
+    // Only relate args to annotations for terms. For leaves, return immediately.
+    // For recursive definitions without annotations (like And and Let), recur.
+    let mut children = vec![];
+    for child in &mut curr.children {
+        children.push(add_rule_constraints(
+            tree,
+            child,
+            termenv,
+            typeenv,
+            annotation_env,
+            annotation_infos,
+            config,
+            rhs,
+        )?);
+    }
+    let e = match &curr.construct {
+        TypeVarConstruct::Var => {
+            tree.quantified_vars
+                .insert(curr.ident.clone(), curr.type_var);
+            tree.free_vars.insert(curr.ident.clone(), curr.type_var);
+            veri_ir::Expr::Terminal(veri_ir::Terminal::Var(curr.ident.clone()))
+        }
+        TypeVarConstruct::BindPattern => {
+            assert_eq!(children.len(), 2);
+            // constant propagate, as for term args: a literal bound to the
+            // variable is the variable's value wherever it's used
+            if let Some(c) = tree.type_var_to_val_map.get(&curr.children[1].type_var) {
+                let c = *c;
+                tree.type_var_to_val_map
+                    .insert(curr.children[0].type_var, c);
+                tree.type_var_to_val_map.insert(curr.type_var, c);
+            }
+            tree.assumptions.push(veri_ir::Expr::Binary(
+                veri_ir::BinaryOp::Eq,
+                Box::new(children[0].clone()),
+                Box::new(children[1].clone()),
+            ));
+            children[0].clone()
+        }
+        TypeVarConstruct::Wildcard(i) => veri_ir::Expr::Terminal(veri_ir::Terminal::Wildcard(*i)),
+        TypeVarConstruct::Const(i) => {
+            // If constant is known, add the value to the tree. Useful for
+            // capturing isleTypes
+            tree.type_var_to_val_map.insert(curr.type_var, *i);
+
+            veri_ir::Expr::Terminal(veri_ir::Terminal::Const(*i, curr.type_var))
+        }
+        TypeVarConstruct::BoolConst(b) => {
+            // Pinned to a boolean rather than typed by context as 1 or 0
+            tree.concrete_constraints
+                .insert(TypeExpr::Concrete(curr.type_var, annotation_ir::Type::Bool));
+            if *b {
+                veri_ir::Expr::Terminal(veri_ir::Terminal::True)
+            } else {
+                veri_ir::Expr::Terminal(veri_ir::Terminal::False)
+            }
+        }
+        TypeVarConstruct::OpaqueConst(ty) => {
+            // Use the model of the constant's declared type if there is one,
+            // otherwise treat it as an integer with an unknown value.
+            let ir_type = annotation_env
+                .model_map
+                .get(ty)
+                .cloned()
+                .unwrap_or(annotation_ir::Type::Int);
+            match ir_type {
+                annotation_ir::Type::BitVector => tree
+                    .bv_constraints
+                    .insert(TypeExpr::Concrete(curr.type_var, ir_type)),
+                _ => tree
+                    .concrete_constraints
+                    .insert(TypeExpr::Concrete(curr.type_var, ir_type)),
+            };
+            tree.quantified_vars
+                .insert(curr.ident.clone(), curr.type_var);
+            tree.free_vars.insert(curr.ident.clone(), curr.type_var);
+            veri_ir::Expr::Terminal(veri_ir::Terminal::Var(curr.ident.clone()))
+        }
+        TypeVarConstruct::And => {
+            tree.quantified_vars
+                .insert(curr.ident.clone(), curr.type_var);
+            let first = &children[0];
+            for (i, e) in children.iter().enumerate() {
+                if i != 0 {
+                    tree.assumptions.push(veri_ir::Expr::Binary(
+                        veri_ir::BinaryOp::Eq,
+                        Box::new(first.clone()),
+                        Box::new(e.clone()),
+                    ))
+                }
+            }
+            first.to_owned()
+        }
+        TypeVarConstruct::Let(bound) => {
+            tree.quantified_vars
+                .insert(curr.ident.clone(), curr.type_var);
+            // A binding has its declared type as well as its value's
+            for (s, declared) in bound.iter() {
+                if let Some(ir_type) = annotation_env.model_map.get(declared) {
+                    let ty_var = tree.quantified_vars[s];
+                    let decl = TypeExpr::Concrete(ty_var, ir_type.clone());
+                    match ir_type {
+                        annotation_ir::Type::BitVector => tree.bv_constraints.insert(decl.clone()),
+                        _ => tree.concrete_constraints.insert(decl.clone()),
+                    };
+                    let var = unescaped_head(s);
+                    tree.let_decls.push((var, decl));
+                }
+            }
+            for (e, (s, _)) in children.iter().zip(bound) {
+                tree.assumptions.push(veri_ir::Expr::Binary(
+                    veri_ir::BinaryOp::Eq,
+                    Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
+                        s.to_owned(),
+                    ))),
+                    Box::new(e.to_owned()),
+                ))
+            }
+            children.last().cloned().expect("let without a body")
+        }
+        TypeVarConstruct::Term(term_id) => {
+            let term = &termenv.terms[term_id.index()];
+            let term_name = typeenv.syms[term.name.index()].clone();
+
+            // Print term for debugging
+            config.reporter.trace(format_args!(" {}", term_name));
+
+            tree.quantified_vars
+                .insert(curr.ident.clone(), curr.type_var);
+            let a = annotation_env.get_annotation_for_term(term_id);
+            if a.is_none() && config.assume_identity && is_pass_through(term) {
+                // The term's value is its argument's
+                let arg = &curr.children[0];
+                if let Some(c) = tree.type_var_to_val_map.get(&arg.type_var) {
+                    tree.type_var_to_val_map.insert(curr.type_var, *c);
+                }
+                tree.var_constraints
+                    .insert(TypeExpr::Variable(curr.type_var, arg.type_var));
+                tree.assumptions.push(veri_ir::Expr::Binary(
+                    veri_ir::BinaryOp::Eq,
+                    Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
+                        curr.ident.clone(),
+                    ))),
+                    Box::new(children[0].clone()),
+                ));
+                let e = veri_ir::Expr::Terminal(veri_ir::Terminal::Var(curr.ident.clone()));
+                tree.ty_vars.insert(e.clone(), curr.type_var);
+                return Ok(e);
+            }
+            if a.is_none() && config.poly_nullary && term.arg_tys.is_empty() {
+                // An unknown value whose type comes only from where it's used
+                tree.free_vars.insert(curr.ident.clone(), curr.type_var);
+                return Ok(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
+                    curr.ident.clone(),
+                )));
+            }
+            let annotation = a.ok_or(TypeError::UnannotatedTerm {
+                term: term_name.clone(),
+            })?;
+            // Each of the term's arguments, or an extractor's outputs, gets
+            // the type of the annotation argument in its position
+            if annotation.sig.args.len() != term.arg_tys.len() {
+                return Err(TypeError::InvalidAnnotation {
+                    term: term_name,
+                    message: format!(
+                        "spec has {} arguments but the term has {}",
+                        annotation.sig.args.len(),
+                        term.arg_tys.len()
+                    ),
+                });
+            }
+
+            // use a fresh mapping for each term
+            // keep the same mapping between assertions in the same annotation
+            let mut annotation_info = AnnotationTypeInfo {
+                term: curr.ident.clone(),
+                var_to_type_var: HashMap::new(),
+                ret: annotation.sig.ret.name.clone(),
+            };
+            for expr in annotation.assumptions {
+                let (typed_expr, _) =
+                    add_annotation_constraints(*expr, tree, &mut annotation_info)?;
+                curr.assertions.push(typed_expr.clone());
+                tree.assumptions.push(typed_expr);
+            }
+            // For assertions, global assume if not RHS, otherwise assert
+            for expr in annotation.assertions {
+                let (typed_expr, _) =
+                    add_annotation_constraints(*expr, tree, &mut annotation_info)?;
+                curr.assertions.push(typed_expr.clone());
+                if rhs {
+                    tree.rhs_assertions.push(typed_expr);
+                } else {
+                    tree.assumptions.push(typed_expr);
+                }
+            }
+
+            // Constrain the annotation's variables by the term's ISLE types, once
+            // the assumptions and assertions have mapped them to type vars
+            add_isle_constraints(
+                term,
+                tree,
+                typeenv,
+                annotation_env,
+                &mut annotation_info,
+                annotation.sig.clone(),
+            );
+
+            // set args in rule equal to args in annotation
+            for (child, arg) in curr.children.iter().zip(&annotation.sig.args) {
+                let rule_type_var = child.type_var;
+                let annotation_type_var = annotation_info.var_to_type_var[&arg.name];
+
+                // essentially constant propagate: if we know the value from the rule arg being
+                // provided as a literal, propagate this to the annotation.
+                if let Some(c) = tree.type_var_to_val_map.get(&rule_type_var) {
+                    tree.type_var_to_val_map.insert(annotation_type_var, *c);
+                }
+                tree.var_constraints
+                    .insert(TypeExpr::Variable(rule_type_var, annotation_type_var));
+            }
+
+            for (child, arg) in children.iter().zip(&annotation.sig.args) {
+                let annotation_type_var = annotation_info.var_to_type_var[&arg.name];
+                let arg_name = format!(
+                    "{}__{}__{}",
+                    escape_name(&annotation_info.term),
+                    escape_name(&arg.name),
+                    annotation_type_var
+                );
+                tree.quantified_vars
+                    .insert(arg_name.clone(), annotation_type_var);
+                tree.assumptions.push(veri_ir::Expr::Binary(
+                    veri_ir::BinaryOp::Eq,
+                    Box::new(child.clone()),
+                    Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(arg_name))),
+                ))
+            }
+            // set term ret var equal to annotation ret var
+            let ret_var = annotation_info.var_to_type_var[&annotation.sig.ret.name];
+            tree.var_constraints
+                .insert(TypeExpr::Variable(curr.type_var, ret_var));
+            let ret_name = format!(
+                "{}__{}__{}",
+                escape_name(&annotation_info.term),
+                escape_name(&annotation.sig.ret.name),
+                ret_var
+            );
+            tree.quantified_vars.insert(ret_name.clone(), ret_var);
+            tree.assumptions.push(veri_ir::Expr::Binary(
+                veri_ir::BinaryOp::Eq,
+                Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
+                    curr.ident.clone(),
+                ))),
+                Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(ret_name))),
+            ));
+
+            annotation_infos.push(annotation_info);
+
+            veri_ir::Expr::Terminal(veri_ir::Terminal::Var(curr.ident.clone()))
+        }
+    };
+    tree.ty_vars.insert(e.clone(), curr.type_var);
+    Ok(e)
+}
+
+pub fn rule_name(rule: &sema::Rule, typeenv: &TypeEnv) -> String {
+    match rule.name {
+        Some(name) => typeenv.syms[name.index()].clone(),
+        None => format!("<unnamed rule {}>", rule.id.index()),
+    }
+}
+
+// Escape a name for one part of a solver-visible variable name, whose parts
+// are joined by `__`. Each `_` is written `_0`, so a `__` only ever joins
+// parts and distinct names can't run together into the same variable.
+fn escape_name(name: &str) -> String {
+    name.replace('_', "_0")
+}
+
+// The first part of a solver-visible variable name, unescaped: the rule or
+// annotation variable it was made for.
+fn unescaped_head(name: &str) -> String {
+    name.split("__").next().unwrap_or(name).replace("_0", "_")
+}
+
+/// Where the rule is defined, as `file:line`.
+pub fn rule_location(rule: &sema::Rule, typeenv: &TypeEnv) -> String {
+    format!("{}:{}", typeenv.filenames[rule.pos.file], rule.pos.line)
+}
+
+// Reject the first type var with a width beyond `max`. One this wide usually
+// comes from a sum or product that was never meant to grow so large.
+fn check_max_width(
+    rule_name: &str,
+    term: &str,
+    max: usize,
+    tys: &HashMap<u32, annotation_ir::Type>,
+) -> Result<(), TypeError> {
+    let too_wide = tys
+        .iter()
+        .filter_map(|(v, ty)| match ty {
+            annotation_ir::Type::BitVectorWithWidth(w) if *w > max => Some((*v, *w)),
+            _ => None,
+        })
+        .min_by_key(|(v, _)| *v);
+    match too_wide {
+        Some((var, width)) => Err(TypeError::WidthTooLarge {
+            rule: rule_name.to_string(),
+            term: term.to_string(),
+            var,
+            width,
+            max,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn solve_constraints(
+    solver: &mut TypeSolver,
+    rule_name: &str,
+    term: &str,
+    concrete: &HashSet<TypeExpr>,
+    var: &HashSet<TypeExpr>,
+    bv: &HashSet<TypeExpr>,
+    vals: &mut HashMap<u32, i128>,
+    // Extended with every type var equal to one of ISLE's `Unit` type
+    units: &mut HashSet<u32>,
+    _lhs_expr: &Expr,
+    _rhs_expr: &Expr,
+    //ty_vars: Option<&HashMap<veri_ir::Expr, u32>>,
+) -> Result<(HashMap<u32, annotation_ir::Type>, Vec<WidthLink>), TypeError> {
+    // Settle plain equalities and concrete types by unification first, so
+    // the solver only sees the classes that width arithmetic depends on.
+    let mut unifier = Unifier::default();
+    let mut residual = vec![];
+    for c in concrete.iter().chain(bv).chain(var) {
+        let unified = match c {
+            TypeExpr::Concrete(v, ty) => unifier.assign(*v, ty, c),
+            TypeExpr::Variable(u, v) => unifier.union(*u, *v, c),
+            TypeExpr::WidthInt(..)
+            | TypeExpr::ConditionalWidthInt(..)
+            | TypeExpr::Symbolic(..)
+            | TypeExpr::WidthAtMost(..)
+            | TypeExpr::Value(..) => {
+                residual.push(c.clone());
+                Ok(())
+            }
+        };
+        if let Err(core) = unified {
+            return Err(TypeError::Unsat {
+                rule: rule_name.to_string(),
+                term: term.to_string(),
+                core,
+            });
+        }
+    }
+
+    // Classes touched by width arithmetic, or with no known type, still need
+    // the solver; every other class is already settled.
+    let mut involved = HashSet::new();
+    for c in &residual {
+        match c {
+            TypeExpr::WidthInt(v, w) | TypeExpr::ConditionalWidthInt(v, w) => {
+                involved.insert(unifier.find(*v));
+                involved.insert(unifier.find(*w));
+            }
+            TypeExpr::Symbolic(l, r) => {
+                for v in l.iter().chain(r) {
+                    involved.insert(unifier.find(*v));
+                }
+            }
+            TypeExpr::WidthAtMost(v, x) => {
+                involved.insert(unifier.find(*v));
+                involved.insert(unifier.find(*x));
+            }
+            TypeExpr::Value(v, _) => {
+                involved.insert(unifier.find(*v));
+            }
+            _ => unreachable!("only width constraints are residual"),
+        }
+    }
+    // Variables that only carry a value still need a type
+    for v in vals.keys() {
+        unifier.find(*v);
+    }
+    for v in units.iter() {
+        unifier.find(*v);
+    }
+
+    let mut result = HashMap::new();
+    let mut unsettled = HashSet::new();
+    for (rep, members) in unifier.classes() {
+        // Unit classes carry no value, so they're left untyped rather than
+        // given a discriminant; constraining them like a value is an error.
+        if members.iter().any(|m| units.contains(m)) {
+            if let Some((_, core)) = unifier.types.get(&rep) {
+                return Err(TypeError::UnitValue {
+                    rule: rule_name.to_string(),
+                    term: term.to_string(),
+                    core: core.clone(),
+                });
+            }
+            if involved.contains(&rep) {
+                let core = residual
+                    .iter()
+                    .filter(|c| c.type_vars().iter().any(|v| members.contains(v)))
+                    .cloned()
+                    .collect();
+                return Err(TypeError::UnitValue {
+                    rule: rule_name.to_string(),
+                    term: term.to_string(),
+                    core,
+                });
+            }
+            units.extend(members);
+            continue;
+        }
+        // Looking for a second typing needs every class in the solver
+        match unifier.class_type(rep) {
+            Some(ty) if !involved.contains(&rep) && !solver.check_unique => {
+                for m in members {
+                    result.insert(m, ty.clone());
+                }
+            }
+            ty => {
+                if let Some(ty) = ty {
+                    residual.push(TypeExpr::Concrete(rep, ty));
+                }
+                for m in members {
+                    residual.push(TypeExpr::Variable(rep, m));
+                    unsettled.insert(m);
+                }
+            }
+        }
+    }
+
+    // Widths settled by unification are held to the same bound as solved ones
+    check_max_width(rule_name, term, solver.max_width, &result)?;
+
+    if !residual.is_empty() {
+        let vals: HashMap<u32, i128> = vals
+            .iter()
+            .filter(|(v, _)| unsettled.contains(*v))
+            .map(|(v, n)| (*v, *n))
+            .collect();
+        // Scope this rule's assertions so the shared context can be reused
+        // for the next rule.
+        let vars: HashSet<u32> = residual
+            .iter()
+            .flat_map(TypeExpr::type_vars)
+            .chain(vals.keys().copied())
+            .collect();
+        solver.push(vars);
+        solver.add_constraints(&residual.into_iter().collect());
+        solver.set_values(&vals);
+
+        let solved = solver.solve(rule_name, term);
+        solver.pop();
+        result.extend(solved?);
+    }
+
+    // Both are solved as a bitvector of unknown width; keep the declared type
+    // where the width stayed unknown, so display can tell them apart.
+    for c in concrete.iter().chain(bv) {
+        if let TypeExpr::Concrete(
+            v,
+            ty @ (annotation_ir::Type::Poly(_) | annotation_ir::Type::BitVectorUnknown(..)),
+        ) = c
+        {
+            if result.get(v) == Some(&annotation_ir::Type::BitVector) {
+                result.insert(*v, ty.clone());
+            }
+        }
+    }
+
+    // Keep which integers are widths, which the types alone don't say
+    let width_links = concrete
+        .iter()
+        .chain(bv)
+        .chain(var)
+        .filter_map(|c| {
+            let (v, w, conditional) = match c {
+                TypeExpr::WidthInt(v, w) => (*v, *w, false),
+                TypeExpr::ConditionalWidthInt(v, w) => (*v, *w, true),
+                _ => return None,
+            };
+            let width = match result.get(&v)? {
+                annotation_ir::Type::BitVectorWithWidth(width) => Some(*width),
+                annotation_ir::Type::BitVector | annotation_ir::Type::BitVectorUnknown(..) => None,
+                annotation_ir::Type::Poly(_) if !conditional => None,
+                // A conditional link to something that isn't a bitvector
+                _ => return None,
+            };
+            Some(WidthLink {
+                bv: v,
+                int: w,
+                width,
+            })
+        })
+        .sorted()
+        .dedup()
+        .collect();
+    Ok((result, width_links))
+}
+
+/// Union-find over type variables, tracking the type of each class and the
+/// constraints that fixed it.
+#[derive(Default)]
+struct Unifier {
+    parent: HashMap<u32, u32>,
+    types: HashMap<u32, (annotation_ir::Type, Vec<TypeExpr>)>,
+}
+
+impl Unifier {
+    fn find(&mut self, v: u32) -> u32 {
+        let mut root = *self.parent.entry(v).or_insert(v);
+        while self.parent[&root] != root {
+            root = self.parent[&root];
+        }
+        // Path compression
+        let mut v = v;
+        while v != root {
+            v = self.parent.insert(v, root).unwrap();
+        }
+        root
+    }
+
+    fn assign(
+        &mut self,
+        v: u32,
+        ty: &annotation_ir::Type,
+        c: &TypeExpr,
+    ) -> Result<(), Vec<TypeExpr>> {
+        let rep = self.find(v);
+        let ty = Self::normalize(ty);
+        let merged = match self.types.remove(&rep) {
+            None => (ty, vec![c.clone()]),
+            Some((old, mut sources)) => {
+                sources.push(c.clone());
+                match Self::meet(&old, &ty) {
+                    Some(ty) => (ty, sources),
+                    None => return Err(sources),
+                }
+            }
+        };
+        self.types.insert(rep, merged);
+        Ok(())
+    }
+
+    fn union(&mut self, u: u32, v: u32, c: &TypeExpr) -> Result<(), Vec<TypeExpr>> {
+        let (a, b) = (self.find(u), self.find(v));
+        if a == b {
+            return Ok(());
+        }
+        self.parent.insert(b, a);
+        match (self.types.remove(&a), self.types.remove(&b)) {
+            (Some((ta, mut sa)), Some((tb, sb))) => {
+                sa.extend(sb);
+                sa.push(c.clone());
+                match Self::meet(&ta, &tb) {
+                    Some(ty) => {
+                        self.types.insert(a, (ty, sa));
+                    }
+                    None => return Err(sa),
+                }
+            }
+            (Some(t), None) | (None, Some(t)) => {
+                self.types.insert(a, t);
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    fn class_type(&self, rep: u32) -> Option<annotation_ir::Type> {
+        self.types.get(&rep).map(|(ty, _)| ty.clone())
+    }
+
+    fn classes(&mut self) -> HashMap<u32, Vec<u32>> {
+        let vars: Vec<u32> = self.parent.keys().copied().collect();
+        let mut classes: HashMap<u32, Vec<u32>> = HashMap::new();
+        for v in vars {
+            let rep = self.find(v);
+            classes.entry(rep).or_default().push(v);
+        }
+        classes
+    }
+
+    // Unknown-width and polymorphic types are bitvectors of unknown width,
+    // as in `TypeSolver::concrete`.
+    fn normalize(ty: &annotation_ir::Type) -> annotation_ir::Type {
+        match ty {
+            annotation_ir::Type::BitVectorUnknown(..) | annotation_ir::Type::Poly(_) => {
+                annotation_ir::Type::BitVector
+            }
+            ty => ty.clone(),
+        }
+    }
+
+    // The most specific type satisfying both, if they're compatible.
+    fn meet(a: &annotation_ir::Type, b: &annotation_ir::Type) -> Option<annotation_ir::Type> {
+        use annotation_ir::Type::*;
+        match (a, b) {
+            (BitVector, BitVectorWithWidth(w)) | (BitVectorWithWidth(w), BitVector) => {
+                Some(BitVectorWithWidth(*w))
+            }
+            _ if a == b => Some(a.clone()),
+            _ => None,
+        }
+    }
+}
+
+pub struct TypeSolver {
+    smt: easy_smt::Context,
+
+    // Symbolic type for each type variable. Type variables are numbered
+    // afresh for each rule, so their declarations and invariants are made
+    // outside the rule's scope once and reused by every later rule.
+    symbolic_types: HashMap<u32, SymbolicType>,
+    // Type variables of the current rule.
+    rule_vars: HashSet<u32>,
+    // Type variables first declared inside the current rule's scope, whose
+    // declarations are dropped with it.
+    scoped_vars: Vec<u32>,
+    // Whether a rule's scope is open.
+    in_scope: bool,
+
+    // Constraints asserted for the current rule, keyed by the label used to
+    // name their assertions, so an unsat core can be mapped back to them.
+    tracked: HashMap<String, TypeExpr>,
+    // Label of the constraint currently being asserted, if any.
+    tracking: Option<String>,
+    // Counter used to give every named assertion a unique name.
+    named_assertions: usize,
+
+    // Time limit for each check, in milliseconds.
+    timeout: Option<u64>,
+
+    // Whether to look for a second typing after each solution.
+    check_unique: bool,
+
+    // Widest bitvector a solution may have.
+    max_width: usize,
+}
+
+impl TypeSolver {
+    // The solver is shared by every rule in the run, and is the only context
+    // that writes the replay file, so the log reflects every solved constraint.
+    pub fn new(config: &Config) -> Self {
+        let (program, args) = config.solver.command();
+        let mut builder = easy_smt::ContextBuilder::new();
+        builder.solver(program, args);
+        if let Some(path) = &config.smt_log {
+            let file = std::fs::File::create(path)
+                .unwrap_or_else(|err| panic!("could not create SMT log '{}': {}", path, err));
+            builder.replay_file(Some(file));
+        }
+        let mut smt = builder.build().unwrap_or_else(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                panic!(
+                    "could not launch solver '{}'; install it or pass --solver",
+                    program
+                )
+            }
+            panic!("could not launch solver '{}': {}", program, err)
+        });
+        smt.set_option(":produce-unsat-cores", smt.true_()).unwrap();
+        if let Some(ms) = config.timeout {
+            smt.set_option(config.solver.timeout_option(), smt.numeral(ms))
+                .unwrap();
+        }
+        Self {
+            smt,
+            symbolic_types: HashMap::new(),
+            rule_vars: HashSet::new(),
+            scoped_vars: vec![],
+            in_scope: false,
+            tracked: HashMap::new(),
+            tracking: None,
+            named_assertions: 0,
+            timeout: config.timeout,
+            check_unique: config.check_unique,
+            max_width: config.max_width,
+        }
+    }
+
+    // Declare the rule's type variables, reusing any declared for an earlier
+    // rule, then open the rule's scope.
+    fn push(&mut self, vars: impl IntoIterator<Item = u32>) {
+        for v in vars {
+            self.get_symbolic_type(v);
+            self.rule_vars.insert(v);
+        }
+        self.smt.push().unwrap();
+        self.in_scope = true;
+    }
+
+    fn pop(&mut self) {
+        self.smt.pop().unwrap();
+        self.in_scope = false;
+        // Declarations made inside the scope went with it.
+        for v in self.scoped_vars.drain(..) {
+            self.symbolic_types.remove(&v);
+        }
+        self.rule_vars.clear();
+        self.tracked.clear();
+        self.named_assertions = 0;
+    }
+
+    fn solve(
+        &mut self,
+        rule_name: &str,
+        term: &str,
+    ) -> Result<HashMap<u32, annotation_ir::Type>, TypeError> {
+        match self.smt.check().unwrap() {
+            Response::Sat => (),
+            Response::Unsat => {
+                return Err(TypeError::Unsat {
+                    rule: rule_name.to_string(),
+                    term: term.to_string(),
+                    core: self.unsat_core(),
+                })
+            }
+            Response::Unknown => match self.timeout {
+                Some(ms) => {
+                    return Err(TypeError::Timeout {
+                        rule: rule_name.to_string(),
+                        term: term.to_string(),
+                        ms,
+                    })
+                }
+                None => panic!("solver returned unknown for rule `{}`", rule_name),
+            },
+        }
+
+        let vs: Vec<_> = self.rule_vars.iter().copied().collect();
+        let mut tys = HashMap::new();
+        for v in vs {
+            tys.insert(v, self.get_type(v));
+        }
+
+        // A width that isn't implied by the constraints is an artifact of the
+        // model, so report it as unknown.
+        for (v, ty) in tys.iter_mut() {
+            if let annotation_ir::Type::BitVectorWithWidth(w) = ty {
+                if !self.width_is_implied(*v, *w) {
+                    *ty = annotation_ir::Type::BitVector;
+                }
+            }
+        }
+
+        // Only widths the constraints imply are left
+        check_max_width(rule_name, term, self.max_width, &tys)?;
+
+        if self.check_unique {
+            if let Some(second) = self.other_solution(&tys) {
+                return Err(TypeError::Ambiguous {
+                    rule: rule_name.to_string(),
+                    term: term.to_string(),
+                    first: tys,
+                    second,
+                });
+            }
+        }
+
+        // Width sums are only enforced once their widths are known.
+        for constraint in self.tracked.values() {
+            if let TypeExpr::Symbolic(l, r) = constraint {
+                if l.iter()
+                    .chain(r)
+                    .any(|v| tys.get(v) == Some(&annotation_ir::Type::BitVector))
+                {
+                    return Err(TypeError::UnknownWidth {
+                        rule: rule_name.to_string(),
+                        term: term.to_string(),
+                        constraint: constraint.clone(),
+                    });
+                }
+            }
+        }
+        Ok(tys)
+    }
+
+    // Block the given solution and look for another one. Only discriminants
+    // and known widths are blocked, since unknown widths may vary freely.
+    fn other_solution(
+        &mut self,
+        tys: &HashMap<u32, annotation_ir::Type>,
+    ) -> Option<HashMap<u32, annotation_ir::Type>> {
+        let mut same = vec![];
+        for (v, ty) in tys {
+            let symbolic_type = self.get_symbolic_type(*v);
+            let (disc, width) = match ty {
+                annotation_ir::Type::BitVectorWithWidth(w) => (
+                    TypeDiscriminant::BitVector,
+                    Some((&symbolic_type.bitvector_width, *w)),
+                ),
+                annotation_ir::Type::Int => (TypeDiscriminant::Int, None),
+                annotation_ir::Type::Bool => (TypeDiscriminant::Bool, None),
+                _ => (TypeDiscriminant::BitVector, None),
+            };
+            same.push(self.smt.eq(
+                symbolic_type.discriminant.expr,
+                self.smt.numeral(disc as u8),
+            ));
+            if let Some((option, w)) = width {
+                same.push(option.some.expr);
+                same.push(self.smt.eq(option.value.expr, self.smt.numeral(w)));
+            }
+        }
+
+        self.smt.push().unwrap();
+        self.smt
+            .assert(self.smt.not(self.smt.and_many(same)))
+            .unwrap();
+        let other = match self.smt.check().unwrap() {
+            Response::Sat => Some(tys.keys().map(|v| (*v, self.get_type(*v))).collect()),
+            _ => None,
+        };
+        self.smt.pop().unwrap();
+        other
+    }
+
+    // Whether every model of the current assertions gives v the width w.
+    fn width_is_implied(&mut self, v: u32, w: usize) -> bool {
+        let width = self.get_symbolic_type(v).bitvector_width;
+        self.smt.push().unwrap();
+        let other_width = self.smt.not(self.smt.and(
+            width.some.expr,
+            self.smt.eq(width.value.expr, self.smt.numeral(w)),
+        ));
+        self.smt.assert(other_width).unwrap();
+        let implied = matches!(self.smt.check().unwrap(), Response::Unsat);
+        self.smt.pop().unwrap();
+        implied
+    }
+
+    fn unsat_core(&mut self) -> Vec<TypeExpr> {
+        let core = self.smt.get_unsat_core().unwrap();
+        let names = match self.smt.get(core) {
+            SExprData::List(names) => names.to_vec(),
+            _ => unreachable!("unsat core should be a list"),
+        };
+
+        // Several assertions may share a constraint; report each one once.
+        let mut constraints = vec![];
+        for name in names {
+            let name = self.smt.display(name).to_string();
+            let label = match name.rsplit_once('_') {
+                Some((label, _)) => label,
+                None => &name,
+            };
+            if let Some(c) = self.tracked.get(label) {
+                if !constraints.contains(c) {
+                    constraints.push(c.clone());
+                }
+            }
+        }
+        constraints
+    }
+
+    fn get_type(&mut self, v: u32) -> annotation_ir::Type {
+        let symbolic_type = self.get_symbolic_type(v);
+
+        // Lookup discriminant from the model.
+        let discriminant_value =
+            usize::try_from(self.get_value_data(symbolic_type.discriminant.expr))
+                .expect("discriminant value should be integer");
+        let discriminant =
+            TypeDiscriminant::from_repr(discriminant_value).expect("unknown discriminant value");
+
+        match discriminant {
+            TypeDiscriminant::BitVector => {
+                // Is the bitvector width known?
+                let has_width = self.get_bool_value(symbolic_type.bitvector_width.some.expr);
+                if !has_width {
+                    return annotation_ir::Type::BitVector;
+                }
+
+                // Lookup width.
+                let width =
+                    usize::try_from(self.get_value_data(symbolic_type.bitvector_width.value.expr))
+                        .expect("bitvector width should be integer");
+
+                annotation_ir::Type::BitVectorWithWidth(width)
+            }
+            TypeDiscriminant::Bool => annotation_ir::Type::Bool,
+            TypeDiscriminant::Int => annotation_ir::Type::Int,
+        }
+    }
+
+    fn get_bool_value(&mut self, expr: SExpr) -> bool {
+        let value = self.get_value(expr);
+        if value == self.smt.true_() {
+            true
+        } else if value == self.smt.false_() {
+            false
+        } else {
+            unreachable!("value is not boolean");
+        }
+    }
+
+    fn get_value_data(&mut self, expr: SExpr) -> SExprData {
+        let value = self.get_value(expr);
+        self.smt.get(value)
+    }
+
+    fn get_value(&mut self, expr: SExpr) -> SExpr {
+        let values = self.smt.get_value(vec![expr]).unwrap();
+        assert_eq!(values.len(), 1);
+        values[0].1
+    }
+
+    fn add_constraints(&mut self, type_exprs: &HashSet<TypeExpr>) {
+        for type_expr in type_exprs {
+            self.add_constraint(type_expr);
+        }
+    }
+
+    fn add_constraint(&mut self, type_expr: &TypeExpr) {
+        // Name every assertion made for this constraint so it can appear in
+        // an unsat core.
+        let label = format!("c{}", self.tracked.len());
+        self.tracked.insert(label.clone(), type_expr.clone());
+        self.tracking = Some(label);
+
+        match type_expr {
+            TypeExpr::Concrete(v, ty) => self.concrete(*v, ty),
+            TypeExpr::Variable(u, v) => self.variable(*u, *v),
+            TypeExpr::WidthInt(v, w) => self.width_int(*v, *w),
+            TypeExpr::ConditionalWidthInt(v, w) => self.conditional_width_int(*v, *w),
+            TypeExpr::Symbolic(l, r) => self.symbolic_sum(l.clone(), r.clone()),
+            TypeExpr::WidthAtMost(v, x) => self.width_at_most(*v, *x),
+            TypeExpr::Value(v, n) => self.set_value(*v, *n),
+        }
+
+        self.tracking = None;
+    }
+
+    fn assert(&mut self, expr: SExpr) {
+        let expr = match &self.tracking {
+            Some(label) => {
+                let name = format!("{}_{}", label, self.named_assertions);
+                self.named_assertions += 1;
+                self.smt.named(name, expr)
+            }
+            None => expr,
+        };
+        self.smt.assert(expr).unwrap();
+    }
+
+    fn set_values(&mut self, vals: &HashMap<u32, i128>) {
+        // Tracked like any constraint, so a value can appear in an unsat core
+        for (v, n) in vals {
+            self.add_constraint(&TypeExpr::Value(*v, *n));
+        }
+    }
+
+    fn set_value(&mut self, v: u32, n: i128) {
+        // If it's an integer or bitvector constant, it should have this value.
+        let symbolic_type = self.get_symbolic_type(v);
+        let is_int = self.smt.eq(
+            symbolic_type.discriminant.expr,
+            self.smt.numeral(TypeDiscriminant::Int as u8),
+        );
+        let is_bitvector = self.smt.eq(
+            symbolic_type.discriminant.expr,
+            self.smt.numeral(TypeDiscriminant::BitVector as u8),
+        );
+        let has_value = self.smt.and(
+            symbolic_type.integer_value.some.expr,
+            self.smt
+                .eq(symbolic_type.integer_value.value.expr, self.smt.numeral(n)),
+        );
+        self.assert(self.smt.imp(self.smt.or(is_int, is_bitvector), has_value));
+    }
+
+    fn concrete(&mut self, v: u32, ty: &annotation_ir::Type) {
+        let symbolic_type = self.get_symbolic_type(v);
+        match ty {
+            // Polymorphic and unknown-width types are encoded as a bitvector
+            // whose width is left unconstrained.
+            annotation_ir::Type::BitVector
+            | annotation_ir::Type::BitVectorUnknown(..)
+            | annotation_ir::Type::Poly(_) => {
+                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::BitVector);
+            }
+            annotation_ir::Type::BitVectorWithWidth(w) => {
+                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::BitVector);
+                self.assert_option_value(&symbolic_type.bitvector_width, self.smt.numeral(*w));
+            }
+            annotation_ir::Type::Int => {
+                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::Int)
+            }
+            annotation_ir::Type::Bool => {
+                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::Bool)
+            }
+        }
+    }
+
+    fn variable(&mut self, u: u32, v: u32) {
+        let a = self.get_symbolic_type(u);
+        let b = self.get_symbolic_type(v);
+        self.assert_types_equal(&a, &b);
+    }
+
+    fn width_int(&mut self, v: u32, w: u32) {
+        // Type v is a bitvector, type w is an integer, and the bitwidth of v is
+        // equal to the value of w.
+        let bitvector_type = self.get_symbolic_type(v);
+        let width_type = self.get_symbolic_type(w);
+
+        self.assert_type_discriminant(&bitvector_type, TypeDiscriminant::BitVector);
+        self.assert_type_discriminant(&width_type, TypeDiscriminant::Int);
+        self.assert_options_equal(&bitvector_type.bitvector_width, &width_type.integer_value)
+    }
+
+    fn conditional_width_int(&mut self, v: u32, w: u32) {
+        // Type w is an integer, and if type v is a bitvector then its width
+        // is equal to the value of w.
+        let operand_type = self.get_symbolic_type(v);
+        let width_type = self.get_symbolic_type(w);
+
+        self.assert_type_discriminant(&width_type, TypeDiscriminant::Int);
+        let is_bitvector = self.smt.eq(
+            operand_type.discriminant.expr,
+            self.smt.numeral(TypeDiscriminant::BitVector as u8),
+        );
+        let width = &operand_type.bitvector_width;
+        let value = &width_type.integer_value;
+        let equal = self.smt.and(
+            self.smt.eq(width.some.expr, value.some.expr),
+            self.smt.eq(width.value.expr, value.value.expr),
+        );
+        self.assert(self.smt.imp(is_bitvector, equal));
+    }
+
+    fn symbolic_sum(&mut self, l: Vec<u32>, r: Vec<u32>) {
+        // get the expressions of each bv we want to add
+        let l_widths: Vec<SymbolicOption> = l
+            .iter()
+            .map(|s| self.get_symbolic_type(*s).bitvector_width)
+            .collect();
+        // sum them together
+        let l_sum = self.smt.plus_many(l_widths.iter().map(|w| w.value.expr));
+        let l_known = self.smt.and_many(l_widths.iter().map(|w| w.some.expr));
+
+        // same for rhs
+        let r_widths: Vec<SymbolicOption> = r
+            .iter()
+            .map(|s| self.get_symbolic_type(*s).bitvector_width)
+            .collect();
+        let r_sum = self.smt.plus_many(r_widths.iter().map(|w| w.value.expr));
+        let r_known = self.smt.and_many(r_widths.iter().map(|w| w.some.expr));
+
+        // An unknown width has value 0, so the sum only constrains the other
+        // side once every width on this side is known. Sums whose widths stay
+        // unknown are reported after solving.
+        let sum_eq = self.smt.eq(l_sum, r_sum);
+        self.assert(self.smt.imp(l_known, self.smt.and(r_known, sum_eq)));
+    }
+
+    fn width_at_most(&mut self, v: u32, x: u32) {
+        let v_width = self.get_symbolic_type(v).bitvector_width;
+        let x_width = self.get_symbolic_type(x).bitvector_width;
+
+        // Only bounded once both widths are known, so an unknown width on
+        // either side doesn't fix the other.
+        let known = self.smt.and(v_width.some.expr, x_width.some.expr);
+        let at_most = self.smt.lte(v_width.value.expr, x_width.value.expr);
+        self.assert(self.smt.imp(known, at_most));
+    }
+
+    fn assert_type_discriminant(&mut self, symbolic_type: &SymbolicType, disc: TypeDiscriminant) {
+        let disc = self.smt.numeral(disc as u8);
+        let eq = self.smt.eq(symbolic_type.discriminant.expr, disc);
+        self.assert(eq);
+    }
+
+    fn assert_option_value(&mut self, symbolic_option: &SymbolicOption, value: SExpr) {
+        self.assert(symbolic_option.some.expr);
+        self.assert(self.smt.eq(symbolic_option.value.expr, value));
+    }
+
+    fn assert_types_equal(&mut self, a: &SymbolicType, b: &SymbolicType) {
+        // Note that we assert nothing about the integer value, only that the
+        // types are the same.
+        self.assert_variables_equal(&a.discriminant, &b.discriminant);
+        self.assert_options_equal(&a.bitvector_width, &b.bitvector_width);
+    }
+
+    fn assert_options_equal(&mut self, a: &SymbolicOption, b: &SymbolicOption) {
+        self.assert_variables_equal(&a.some, &b.some);
+        self.assert_variables_equal(&a.value, &b.value);
+    }
+
+    fn assert_variables_equal(&mut self, a: &SymbolicVariable, b: &SymbolicVariable) {
+        self.assert(self.smt.eq(a.expr, b.expr));
+    }
+
+    fn get_symbolic_type(&mut self, v: u32) -> SymbolicType {
+        if !self.symbolic_types.contains_key(&v) && self.in_scope {
+            self.scoped_vars.push(v);
+            self.rule_vars.insert(v);
+        }
+        self.symbolic_types
+            .entry(v)
+            .or_insert_with(|| SymbolicType::decl(&mut self.smt, v))
+            .clone()
+    }
+    // The rule as valid ISLE, preceded by comments showing its typed form.
+    fn display_isle_rule(
+        &mut self,
+        termenv: &TermEnv,
+        typeenv: &TypeEnv,
+        rule: &sema::Rule,
+        annotation_infos: &[AnnotationTypeInfo],
+        type_sols: &HashMap<u32, veri_ir::annotation_ir::Type>,
+        units: &HashSet<u32>,
+        lhs: &TypeVarNode,
+        iflets: &[(TypeVarNode, TypeVarNode)],
+        rhs: &TypeVarNode,
+    ) -> String {
+        let root = Pattern::Term(
+            cranelift_isle::sema::TypeId(0),
+            rule.root_term,
+            rule.args.clone(),
+        );
+        let mut render = |style| {
+            let lhs = self.display_isle_pattern(
+                termenv,
+                typeenv,
+                rule,
+                annotation_infos,
+                &[],
+                type_sols,
+                units,
+                style,
+                &root,
+                lhs,
+                None,
+            );
+            let iflets = rule
+                .iflets
+                .iter()
+                .zip(iflets)
+                .map(|(iflet, (pat_node, expr_node))| {
+                    let pat = self.display_isle_pattern(
+                        termenv,
+                        typeenv,
+                        rule,
+                        annotation_infos,
+                        &[],
+                        type_sols,
+                        units,
+                        style,
+                        &iflet.lhs,
+                        pat_node,
+                        None,
+                    );
+                    let expr = self.display_isle_expr(
+                        termenv,
+                        typeenv,
+                        rule,
+                        annotation_infos,
+                        &[],
+                        type_sols,
+                        units,
+                        style,
+                        &iflet.rhs,
+                        expr_node,
+                        None,
+                    );
+                    self.smt.list(vec![self.smt.atom("if-let"), pat, expr])
+                })
+                .collect::<Vec<SExpr>>();
+            let rhs = self.display_isle_expr(
+                termenv,
+                typeenv,
+                rule,
+                annotation_infos,
+                &[],
+                type_sols,
+                units,
+                style,
+                &rule.rhs,
+                rhs,
+                None,
+            );
+            (lhs, iflets, rhs)
+        };
+
+        let (typed_lhs, typed_iflets, typed_rhs) = render(DisplayStyle::Typed);
+        let (lhs, iflets, rhs) = render(DisplayStyle::Isle);
+
+        let mut out = String::new();
+        out.push_str(&format!(";; {}\n", self.smt.display(typed_lhs)));
+        for iflet in typed_iflets {
+            out.push_str(&format!(";; {}\n", self.smt.display(iflet)));
+        }
+        out.push_str(&format!(";; => {}\n", self.smt.display(typed_rhs)));
+
+        let mut sexprs = vec![self.smt.atom("rule")];
+        if let Some(name) = rule.name {
+            sexprs.push(self.smt.atom(&typeenv.syms[name.index()]));
+        }
+        if rule.prio != 0 {
+            sexprs.push(self.smt.atom(rule.prio.to_string()));
+        }
+        sexprs.push(lhs);
+        sexprs.extend(iflets);
+        sexprs.push(rhs);
+        out.push_str(&format!("{}\n", self.smt.display(self.smt.list(sexprs))));
+        out
+    }
+
+    fn display_isle_pattern(
+        &mut self,
+        termenv: &TermEnv,
+        typeenv: &TypeEnv,
+        rule: &sema::Rule,
+        annotation_infos: &[AnnotationTypeInfo],
+        // Indices of annotations already matched by an enclosing term
+        removed: &[usize],
+        type_sols: &HashMap<u32, veri_ir::annotation_ir::Type>,
+        units: &HashSet<u32>,
+        style: DisplayStyle,
+        pat: &Pattern,
+        // The parse tree node built from `pat`, which mirrors its structure
+        node: &TypeVarNode,
+        parent_term: Option<&AnnotationTypeInfo>,
+    ) -> SExpr {
+        let mut to_sexpr = |r: &[usize], p, n, pt| {
+            self.display_isle_pattern(
+                termenv,
+                typeenv,
+                rule,
+                annotation_infos,
+                r,
+                type_sols,
+                units,
+                style,
+                p,
+                n,
+                pt,
+            )
+        };
+
+        match pat {
+            sema::Pattern::Term(_, term_id, args) => {
+                let sym = termenv.terms[term_id.index()].name;
+                let name = typeenv.syms[sym.index()].clone();
+
+                let matches: Vec<&AnnotationTypeInfo> = annotation_infos
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, t)| !removed.contains(i) && unescaped_head(&t.term) == name)
+                    .map(|(_, t)| t)
+                    .collect();
+
+                let mut child_removed = Cow::Borrowed(removed);
+                let var = style.label(
+                    display_type(
+                        type_sols,
+                        units,
+                        matches.first().copied(),
+                        &[matches.first().map_or("result", |m| m.ret.as_str())],
+                        node,
+                    ),
+                    &name,
+                );
+                if matches.len() > 1 {
+                    let index = annotation_infos
+                        .iter()
+                        .position(|t| t.term == matches.first().unwrap().term)
+                        .unwrap();
+                    child_removed = Cow::Owned([removed, &[index]].concat());
+                }
+
+                let mut sexprs: Vec<SExpr> = args
+                    .iter()
+                    .zip(&node.children)
+                    .map(|(a, n)| to_sexpr(&*child_removed, a, n, matches.first().copied()))
+                    .collect::<Vec<SExpr>>();
+
+                sexprs.insert(0, self.smt.atom(var));
+                self.smt.list(sexprs)
+            }
+
+            sema::Pattern::Var(_, var_id) => {
+                let sym = rule.vars[var_id.index()].name;
+                let ident = typeenv.syms[sym.index()].clone();
+
+                let var = style.label(
+                    display_type(type_sols, units, parent_term, &[ident.as_str()], node),
+                    &ident,
+                );
+
+                self.smt.atom(var)
+            }
+            sema::Pattern::BindPattern(_, var_id, subpat) => {
+                let sym = rule.vars[var_id.index()].name;
+                let ident = &typeenv.syms[sym.index()];
+                let subpat_node = to_sexpr(removed, subpat, &node.children[1], parent_term);
+
+                let var = style.label(
+                    display_type(
+                        type_sols,
+                        units,
+                        parent_term,
+                        &[ident.as_str(), "arg"],
+                        node,
+                    ),
+                    ident,
+                );
+                // Special case: elide bind patterns to wildcars
+                if matches!(**subpat, sema::Pattern::Wildcard(_)) {
+                    self.smt.atom(&var)
+                } else if style == DisplayStyle::Isle {
+                    // ISLE binds without parentheses, as `x @ (pat)`
+                    let subpat = self.smt.display(subpat_node);
+                    self.smt.atom(format!("{} @ {}", var, subpat))
+                } else {
+                    self.smt
+                        .list(vec![self.smt.atom(&var), self.smt.atom("@"), subpat_node])
+                }
+            }
+            sema::Pattern::Wildcard(_) => match style {
+                DisplayStyle::Typed => self.smt.list(vec![self.smt.atom("_")]),
+                DisplayStyle::Isle => self.smt.atom("_"),
+            },
+            sema::Pattern::ConstPrim(_, sym) => {
+                let mut name = typeenv.syms[sym.index()].clone();
+                // ISLE spells constants with a `$` prefix
+                if style == DisplayStyle::Isle {
+                    name.insert(0, '$');
+                }
+                self.smt
+                    .atom(style.label(const_type(type_sols, units, node), &name))
+            }
+            sema::Pattern::ConstInt(_, num) => self
+                .smt
+                .atom(style.label(const_type(type_sols, units, node), &num.to_string())),
+            sema::Pattern::And(_, subpats) => {
+                let mut sexprs = subpats
+                    .iter()
+                    .zip(&node.children)
+                    .map(|(a, n)| to_sexpr(removed, a, n, parent_term))
+                    .collect::<Vec<SExpr>>();
+
+                sexprs.insert(0, self.smt.atom("and"));
+                self.smt.list(sexprs)
+            }
+        }
+    }
+    fn display_isle_expr(
+        &self,
+        termenv: &TermEnv,
+        typeenv: &TypeEnv,
+        rule: &sema::Rule,
+        annotation_infos: &[AnnotationTypeInfo],
+        // Indices of annotations already matched by an enclosing term
+        removed: &[usize],
+        type_sols: &HashMap<u32, veri_ir::annotation_ir::Type>,
+        units: &HashSet<u32>,
+        style: DisplayStyle,
+        expr: &sema::Expr,
+        // The parse tree node built from `expr`, which mirrors its structure
+        node: &TypeVarNode,
+        parent_term: Option<&AnnotationTypeInfo>,
+    ) -> SExpr {
+        let to_sexpr = |r: &[usize], e, n, pt| {
+            self.display_isle_expr(
+                termenv,
+                typeenv,
+                rule,
+                annotation_infos,
+                r,
+                type_sols,
+                units,
+                style,
+                e,
+                n,
+                pt,
+            )
+        };
+
+        match expr {
+            sema::Expr::Term(_, term_id, args) => {
+                let sym = termenv.terms[term_id.index()].name;
+                let name = typeenv.syms[sym.index()].clone();
+
+                let matches: Vec<&AnnotationTypeInfo> = annotation_infos
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, t)| !removed.contains(i) && unescaped_head(&t.term) == name)
+                    .map(|(_, t)| t)
+                    .collect();
+
+                let mut child_removed = Cow::Borrowed(removed);
+                let var = style.label(
+                    display_type(
+                        type_sols,
+                        units,
+                        matches.first().copied(),
+                        &[matches.first().map_or("result", |m| m.ret.as_str())],
+                        node,
+                    ),
+                    &name,
+                );
+                if matches.len() > 1 {
+                    let index = annotation_infos
+                        .iter()
+                        .position(|t| t.term == matches.first().unwrap().term)
+                        .unwrap();
+                    child_removed = Cow::Owned([removed, &[index]].concat());
+                }
+
+                let mut sexprs = args
+                    .iter()
+                    .zip(&node.children)
+                    .map(|(a, n)| to_sexpr(&*child_removed, a, n, matches.first().copied()))
+                    .collect::<Vec<SExpr>>();
+                sexprs.insert(0, self.smt.atom(var));
+                self.smt.list(sexprs)
+            }
+            sema::Expr::Var(_, var_id) => {
+                let sym = rule.vars[var_id.index()].name;
+                let ident = typeenv.syms[sym.index()].clone();
+
+                let var = style.label(
+                    display_type(
+                        type_sols,
+                        units,
+                        parent_term,
+                        &[ident.as_str(), "arg"],
+                        node,
+                    ),
+                    &ident,
+                );
+
+                self.smt.atom(var)
+            }
+            sema::Expr::ConstPrim(_, sym) => {
+                let mut name = typeenv.syms[sym.index()].clone();
+                // ISLE spells constants with a `$` prefix
+                if style == DisplayStyle::Isle {
+                    name.insert(0, '$');
+                }
+                self.smt
+                    .atom(style.label(const_type(type_sols, units, node), &name))
+            }
+            sema::Expr::ConstInt(_, num) => self
+                .smt
+                .atom(style.label(const_type(type_sols, units, node), &num.to_string())),
+            sema::Expr::Let { bindings, body, .. } => {
+                let mut sexprs = vec![];
+                for ((varid, ty, expr), n) in bindings.iter().zip(&node.children) {
+                    let sym = rule.vars[varid.index()].name;
+                    let ident = typeenv.syms[sym.index()].clone();
+
+                    let mut binding = vec![self.smt.atom(ident)];
+                    // ISLE requires the bound variable's type
+                    if style == DisplayStyle::Isle {
+                        let ty = typeenv.types[ty.index()].name(typeenv);
+                        binding.push(self.smt.atom(ty));
+                    }
+                    binding.push(to_sexpr(removed, expr, n, parent_term));
+                    sexprs.push(self.smt.list(binding));
+                }
+                // The body's node follows the bindings'
+                let body_node = node.children.last().unwrap();
+                self.smt.list(vec![
+                    self.smt.atom("let"),
+                    self.smt.list(sexprs),
+                    to_sexpr(removed, body, body_node, parent_term),
+                ])
+            }
+        }
+    }
+}
+
+// The solved type of the first of `vars` bound by the annotation, for display.
+// A variable the annotation names differently, e.g. `x` passed as `a`, is
+// shown with its node's type. Display is cosmetic, so anything missing is
+// shown as `?`.
+fn display_type(
+    type_sols: &HashMap<u32, annotation_ir::Type>,
+    units: &HashSet<u32>,
+    info: Option<&AnnotationTypeInfo>,
+    vars: &[&str],
+    node: &TypeVarNode,
+) -> String {
+    match info {
+        Some(info) => vars
+            .iter()
+            .find_map(|v| info.var_to_type_var.get(*v))
+            .filter(|t| type_sols.contains_key(t) || units.contains(t))
+            .map_or_else(
+                || const_type(type_sols, units, node),
+                |t| solved_type(type_sols, units, *t),
+            ),
+        // Terms assumed to be the identity have no annotation to name their
+        // variables, so use the node's own type
+        None => const_type(type_sols, units, node),
+    }
+}
+
+// The solved type of a constant, so bitvector literals are distinguishable
+// from integers in the displayed rule.
+fn const_type(
+    type_sols: &HashMap<u32, annotation_ir::Type>,
+    units: &HashSet<u32>,
+    node: &TypeVarNode,
+) -> String {
+    solved_type(type_sols, units, node.type_var)
+}
+
+// The displayed type of a type var: its solution, `unit` for values of
+// ISLE's `Unit` type, or `?` if it has neither.
+fn solved_type(
+    type_sols: &HashMap<u32, annotation_ir::Type>,
+    units: &HashSet<u32>,
+    t: u32,
+) -> String {
+    match type_sols.get(&t) {
+        Some(ty) => type_to_num(ty),
+        None if units.contains(&t) => "unit".to_string(),
+        None => "?".to_string(),
+    }
+}
+
+// How the rule display renders each node.
+#[derive(Clone, Copy, PartialEq)]
+enum DisplayStyle {
+    // Every node labeled with its solved type, as `[type|name]`
+    Typed,
+    // Syntactically valid ISLE, without types
+    Isle,
+}
+
+impl DisplayStyle {
+    fn label(self, ty: String, name: &str) -> String {
+        match self {
+            DisplayStyle::Typed => format!("[{}|{}]", ty, name),
+            DisplayStyle::Isle => name.to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SymbolicVariable {
+    name: String,
+    expr: SExpr,
+}
+
+impl SymbolicVariable {
+    fn integer(smt: &mut easy_smt::Context, name: String) -> Self {
+        Self::decl_sort(smt, name, smt.int_sort())
+    }
+
+    fn boolean(smt: &mut easy_smt::Context, name: String) -> Self {
+        Self::decl_sort(smt, name, smt.bool_sort())
+    }
+
+    fn decl_sort(smt: &mut easy_smt::Context, name: String, sort: SExpr) -> Self {
+        smt.declare_const(name.clone(), sort).unwrap();
+        let expr = smt.atom(name.clone());
+        Self { name, expr }
+    }
+}
+
+#[derive(Clone)]
+struct SymbolicOption {
+    some: SymbolicVariable,
+    value: SymbolicVariable,
+}
+
+impl SymbolicOption {
+    fn decl(smt: &mut easy_smt::Context, value: SymbolicVariable) -> Self {
+        let some = SymbolicVariable::boolean(smt, format!("{}_some", value.name));
+        Self { some, value }
+    }
+}
+
+#[derive(EnumIter, FromRepr, Debug)]
+enum TypeDiscriminant {
+    BitVector = 1,
+    Int = 2,
+    Bool = 3,
+}
+
+#[derive(Clone)]
+struct SymbolicType {
+    discriminant: SymbolicVariable,
+    bitvector_width: SymbolicOption,
+    integer_value: SymbolicOption,
+}
+
+impl SymbolicType {
+    fn decl(smt: &mut easy_smt::Context, v: u32) -> Self {
+        let prefix = format!("t{}", v);
+
+        // Disciminant.
+        let discriminant = SymbolicVariable::integer(smt, format!("{prefix}_disc"));
+
+        // Invariant: discriminant is one of the allowed values
+        smt.assert(smt.or_many(
+            TypeDiscriminant::iter().map(|d| smt.eq(discriminant.expr, smt.numeral(d as u8))),
+        ))
+        .unwrap();
+
+        // Bitvector width (option).
+        let bitvector_width_value =
+            SymbolicVariable::integer(smt, format!("{prefix}_bitvector_width"));
+        let bitvector_width = SymbolicOption::decl(smt, bitvector_width_value);
+
+        // Invariant: if not bitvector then bitvector width option is none.
+        smt.assert(smt.imp(
+            smt.distinct(
+                discriminant.expr,
+                smt.numeral(TypeDiscriminant::BitVector as u8),
+            ),
+            smt.not(bitvector_width.some.expr),
+        ))
+        .unwrap();
+
+        // Invariant: if bitvector width option is none, then its value is 0.
+        smt.assert(smt.imp(
+            smt.not(bitvector_width.some.expr),
+            smt.eq(bitvector_width.value.expr, smt.numeral(0)),
+        ))
+        .unwrap();
+
+        // Integer value. Bitvector constants also record their value here.
+        let integer_value_value = SymbolicVariable::integer(smt, format!("{prefix}_integer_value"));
+        let integer_value = SymbolicOption::decl(smt, integer_value_value);
+
+        // Invariant: if neither integer nor bitvector then integer value
+        // option is none.
+        smt.assert(smt.imp(
+            smt.and(
+                smt.distinct(discriminant.expr, smt.numeral(TypeDiscriminant::Int as u8)),
+                smt.distinct(
+                    discriminant.expr,
+                    smt.numeral(TypeDiscriminant::BitVector as u8),
+                ),
+            ),
+            smt.not(integer_value.some.expr),
+        ))
+        .unwrap();
+
+        // Invariant: if integer value option is none, then its value is 0.
+        smt.assert(smt.imp(
+            smt.not(integer_value.some.expr),
+            smt.eq(integer_value.value.expr, smt.numeral(0)),
+        ))
+        .unwrap();
+
+        Self {
+            discriminant,
+            bitvector_width,
+            integer_value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_sums_dedup_regardless_of_operand_order() {
+        let mut constraints = HashSet::new();
+        constraints.insert(TypeExpr::symbolic(vec![3, 1, 2], vec![4]));
+        constraints.insert(TypeExpr::symbolic(vec![2, 3, 1], vec![4]));
+        constraints.insert(TypeExpr::symbolic(vec![1, 2], vec![4]));
+        assert_eq!(constraints.len(), 2);
+        assert!(constraints.contains(&TypeExpr::Symbolic(vec![1, 2, 3], vec![4])));
+    }
+
+    // A rule with just the bind pattern `x @ p`, where `x` and `p` are
+    // otherwise constrained to the given types.
+    fn check_bind(
+        bound: annotation_ir::Type,
+        pattern: annotation_ir::Type,
+    ) -> Result<(), TypeError> {
+        let tree = RuleParseTree {
+            concrete_constraints: HashSet::from([
+                TypeExpr::Concrete(1, bound),
+                TypeExpr::Concrete(2, pattern),
+            ]),
+            var_constraints: HashSet::from([
+                TypeExpr::Variable(1, 2),
+                TypeExpr::Variable(3, 1),
+                TypeExpr::Variable(3, 2),
+            ]),
+            bind_patterns: vec![("x".to_string(), 1, 2, 3)],
+            ..Default::default()
+        };
+        check_bind_patterns("r", &tree)
+    }
+
+    #[test]
+    fn bind_patterns_accept_compatible_types() {
+        let bv16 = annotation_ir::Type::BitVectorWithWidth(16);
+        for pattern in [
+            bv16.clone(),
+            annotation_ir::Type::BitVector,
+            annotation_ir::Type::BitVectorUnknown(0),
+            annotation_ir::Type::Poly(0),
+        ] {
+            assert!(check_bind(bv16.clone(), pattern).is_ok());
+        }
+    }
+
+    #[test]
+    fn bind_patterns_reject_conflicting_types() {
+        let err = check_bind(
+            annotation_ir::Type::BitVectorWithWidth(16),
+            annotation_ir::Type::BitVectorWithWidth(8),
+        );
+        assert!(matches!(
+            err,
+            Err(TypeError::BindMismatch {
+                bound: annotation_ir::Type::BitVectorWithWidth(16),
+                pattern: annotation_ir::Type::BitVectorWithWidth(8),
+                ..
+            })
+        ));
+        assert!(matches!(
+            check_bind(annotation_ir::Type::Int, annotation_ir::Type::Poly(0)),
+            Err(TypeError::BindMismatch { .. })
+        ));
+    }
+
+    // The defaults of the command line, with no SMT log.
+    fn test_config() -> Config {
+        Config {
+            term: String::new(),
+            names: None,
+            names_regex: false,
+            exclude_names: vec![],
+            smt_log: None,
+            solver: SolverKind::Z3,
+            parallel: false,
+            reporter: Reporter::new(Verbosity::Silent),
+            timeout: None,
+            check_unique: false,
+            dump_constraints: false,
+            emit_isle: false,
+            emit_dot: false,
+            assume_identity: false,
+            stats: false,
+            target: TargetConfig::default(),
+            strict_conv_to: false,
+            switch_coverage: None,
+            narrow_shift_amounts: false,
+            max_width: 512,
+            poly_nullary: false,
+            explain: None,
+        }
+    }
+
+    #[test]
+    fn poly_types_solve_as_bitvectors() {
+        let mut solver = TypeSolver::new(&test_config());
+        solver.push([0, 1, 2]);
+        solver.concrete(0, &annotation_ir::Type::Poly(0));
+        solver.concrete(1, &annotation_ir::Type::BitVectorUnknown(0));
+        solver.concrete(2, &annotation_ir::Type::BitVectorWithWidth(8));
+        solver.variable(0, 2);
+        let tys = solver.solve("r", "A").unwrap();
+        solver.pop();
+        assert_eq!(tys[&0], annotation_ir::Type::BitVectorWithWidth(8));
+        assert_eq!(tys[&1], annotation_ir::Type::BitVector);
+    }
+
+    #[test]
+    fn unbound_rhs_vars_are_errors() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test/concat.isle");
+        let program = crate::parse_program(&[path]).unwrap();
+        // ISLE rejects unbound variables, so drop the rule's bindings instead
+        let mut rule = program.termenv.rules[0].clone();
+        rule.vars.clear();
+        let err = create_parse_tree_expr(
+            &rule,
+            &rule.rhs,
+            &mut RuleParseTree::default(),
+            &program.typeenv,
+            &program.termenv,
+        );
+        assert!(matches!(err, Err(TypeError::UnboundVar { var: 0, .. })));
+    }
+
+    #[test]
+    fn self_referential_lets_are_errors() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test/let_mismatch.isle");
+        let program = crate::parse_program(&[path]).unwrap();
+        // ISLE rejects a let that uses itself, so rebind `y` to itself instead
+        let mut rule = program.termenv.rules[0].clone();
+        let sema::Expr::Let { bindings, .. } = &mut rule.rhs else {
+            panic!("expected a let");
+        };
+        let (y, ty, value) = &mut bindings[0];
+        *value = Box::new(sema::Expr::Var(*ty, *y));
+        let err = create_parse_tree_expr(
+            &rule,
+            &rule.rhs,
+            &mut RuleParseTree::default(),
+            &program.typeenv,
+            &program.termenv,
+        );
+        match err {
+            Err(TypeError::CyclicLet { rule, var, uses }) => {
+                assert_eq!(
+                    (rule.as_str(), var.as_str(), uses.as_str()),
+                    ("widen", "y", "y")
+                );
+            }
+            _ => panic!("expected a cyclic let"),
+        }
+    }
+
+    #[test]
+    fn unknown_widths_and_poly_types_display_differently() {
+        let mut solver = TypeSolver::new(&test_config());
+        let concrete = HashSet::from([
+            TypeExpr::Concrete(0, annotation_ir::Type::Poly(0)),
+            TypeExpr::Concrete(1, annotation_ir::Type::BitVectorUnknown(0)),
+        ]);
+        let x = Expr::Terminal(veri_ir::Terminal::Var("x".to_string()));
+        let (tys, _) = solve_constraints(
+            &mut solver,
+            "r",
+            "A",
+            &concrete,
+            &HashSet::new(),
+            &HashSet::new(),
+            &mut HashMap::new(),
+            &x,
+            &x,
+        )
+        .unwrap();
+        assert_eq!(type_to_num(&tys[&0]), "poly");
+        assert_eq!(type_to_num(&tys[&1]), "bvunk");
+    }
+
+    #[test]
+    fn vir_types_round_trip() {
+        for ty in [
+            Type::BitVector(None),
+            Type::BitVector(Some(1)),
+            Type::BitVector(Some(64)),
+            Type::Int,
+            Type::Bool,
+        ] {
+            let aty = annotation_type_for_vir_type(&ty);
+            assert_eq!(vir_type_for_annotation_type(&aty).as_ref(), Ok(&ty));
+            assert_eq!(vir_type_for_annotation_type_lossy(&aty), ty);
+        }
+    }
+
+    #[test]
+    fn lossy_annotation_types_become_unknown_width() {
+        for aty in [
+            annotation_ir::Type::BitVectorUnknown(0),
+            annotation_ir::Type::Poly(0),
+        ] {
+            assert_eq!(
+                vir_type_for_annotation_type(&aty),
+                Err(LossyTypeConversion(aty.clone()))
+            );
+            assert_eq!(
+                vir_type_for_annotation_type_lossy(&aty),
+                Type::BitVector(None)
+            );
+        }
+    }
+}
@@ -1,33 +1,309 @@
 pub mod annotations;
+pub mod concrete;
+pub mod inference;
+pub mod report;
 pub mod termname;
+use annotations::{parse_annotations, AnnotationEnv};
 use cranelift_codegen_meta::isa::Isa;
-use std::path::PathBuf;
+use cranelift_isle::lexer::Lexer;
+use cranelift_isle::parser::parse;
+use cranelift_isle::sema::{RuleId, TermEnv, TypeEnv};
+use inference::try_type_rules_with_term_and_types;
+pub use inference::{
+    annotation_type_for_vir_type, infer_types, try_infer_types, vir_type_for_annotation_type,
+    vir_type_for_annotation_type_lossy, AnnotationTypeInfo, Config, LossyTypeConversion,
+    Provenance, RuleSemantics, RuleStats, SolverKind, TypeError, TypeExpr, TypeSolver, WidthLink,
+};
+pub use report::{Reporter, Verbosity};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use veri_ir::{ConcreteTest, TermSignature};
 
-pub const REG_WIDTH: usize = 64;
+/// Widths that depend on the target architecture. The default is a 64-bit
+/// target with 4 flag bits, as on aarch64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TargetConfig {
+    /// Width of a general-purpose register, used by `RegWidth`
+    pub reg_width: usize,
+    /// Width of the flags a flag-setting instruction appends to its result
+    pub flags_width: usize,
+}
+
+impl TargetConfig {
+    /// Width of the value of a flag-setting subtract (`subs`): the register
+    /// result with the flags appended above it.
+    pub fn subs_width(&self) -> usize {
+        self.reg_width + self.flags_width
+    }
+
+    /// The `(high, low)` bits of a `subs` value holding the register result.
+    pub fn subs_result_bits(&self) -> (usize, usize) {
+        (self.reg_width - 1, 0)
+    }
+
+    /// The `(high, low)` bits of a `subs` value holding the flags, NZCV on
+    /// aarch64.
+    pub fn subs_flags_bits(&self) -> (usize, usize) {
+        (self.subs_width() - 1, self.reg_width)
+    }
 
-pub const FLAGS_WIDTH: usize = 4;
+    /// Split a `subs` value into its register result and its flags.
+    pub fn split_subs(&self, subs: veri_ir::Expr) -> (veri_ir::Expr, veri_ir::Expr) {
+        let (result_hi, result_lo) = self.subs_result_bits();
+        let (flags_hi, flags_lo) = self.subs_flags_bits();
+        (
+            veri_ir::Expr::BVExtract(result_hi, result_lo, Box::new(subs.clone())),
+            veri_ir::Expr::BVExtract(flags_hi, flags_lo, Box::new(subs)),
+        )
+    }
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        Self {
+            reg_width: 64,
+            flags_width: 4,
+        }
+    }
+}
 
-pub fn build_clif_lower_isle() -> PathBuf {
-    // Build the relevant ISLE prelude using the meta crate
-    let out_dir = "veri-isle-clif-gen";
+#[derive(Debug)]
+pub enum BuildError {
+    /// The output directory for the generated ISLE couldn't be created.
+    CreateDir(String),
+    /// The meta crate failed to generate the ISLE files.
+    MetaGenerate(String),
+    /// The meta crate fingerprint couldn't be recorded next to the generated
+    /// ISLE.
+    WriteFingerprint(String),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BuildError::CreateDir(msg) => write!(
+                f,
+                "could not create directory for CLIF ISLE meta-generated code: {}",
+                msg
+            ),
+            BuildError::MetaGenerate(msg) => write!(f, "meta generate error: {}", msg),
+            BuildError::WriteFingerprint(msg) => {
+                write!(f, "could not record the meta crate fingerprint: {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The ISLE files couldn't be read or parsed.
+    Parse(String),
+    /// The ISLE files' types or terms are ill-formed.
+    Sema(String),
+    /// The term to type has no instantiations to type it with.
+    MissingTerm(String),
+    /// The annotations name a term or type that isn't declared, or give a
+    /// term more than one spec.
+    Annotation(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Parse(msg) => write!(f, "could not parse ISLE: {}", msg),
+            Error::Sema(msg) => write!(f, "ISLE type-definition error: {}", msg),
+            Error::MissingTerm(term) => write!(f, "missing term width for {}", term),
+            Error::Annotation(msg) => write!(f, "invalid annotations: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A parsed ISLE program with its types, terms, and annotations resolved.
+pub struct Program {
+    pub typeenv: TypeEnv,
+    pub termenv: TermEnv,
+    pub annotation_env: AnnotationEnv,
+    // The instantiations of each term by name, built on first use. Adding
+    // annotations with `merge_annotations` clears it.
+    term_signatures: OnceCell<HashMap<String, Vec<TermSignature>>>,
+}
+
+impl Program {
+    /// The instantiations of each term, by name. The map is built once and
+    /// reused for every term typed.
+    pub fn term_signatures(&self) -> &HashMap<String, Vec<TermSignature>> {
+        self.term_signatures.get_or_init(|| {
+            self.annotation_env
+                .get_term_signatures_by_name(&self.termenv, &self.typeenv)
+        })
+    }
+
+    /// Add annotations from another file, as `AnnotationEnv::merge` does. A
+    /// term with a spec in both is an error naming it.
+    pub fn merge_annotations(&mut self, other: AnnotationEnv) -> Result<(), Error> {
+        self.annotation_env.merge(other).map_err(|term_id| {
+            let sym = self.termenv.terms[term_id.index()].name;
+            Error::Annotation(format!(
+                "term `{}` has a spec in more than one file",
+                self.typeenv.syms[sym.index()]
+            ))
+        })?;
+        self.term_signatures.take();
+        Ok(())
+    }
+
+    /// Parse a file of annotations kept apart from the rules and add them, as
+    /// `merge_annotations` does. A parse error gives the file and position,
+    /// and any other error the file and the symbol at fault.
+    pub fn merge_annotation_file(&mut self, path: &Path) -> Result<(), Error> {
+        let lexer = Lexer::from_files([path]).map_err(|err| Error::Parse(format!("{:?}", err)))?;
+        let defs = parse(lexer).map_err(|err| Error::Parse(format!("{:?}", err)))?;
+        let in_file = |msg| format!("{}: {}", path.display(), msg);
+        let annotations = parse_annotations(&defs, &self.termenv, &self.typeenv)
+            .map_err(|msg| Error::Annotation(in_file(msg)))?;
+        self.merge_annotations(annotations)
+            .map_err(|err| match err {
+                Error::Annotation(msg) => Error::Annotation(in_file(msg)),
+                err => err,
+            })
+    }
+
+    /// Type the rules rooted at `config.term`, with one map of typed rules
+    /// per instantiation of the term, in the order they are declared.
+    pub fn infer(&self, config: &Config) -> Result<Vec<HashMap<RuleId, RuleSemantics>>, Error> {
+        let instantiations = self
+            .term_signatures()
+            .get(&config.term)
+            .ok_or_else(|| Error::MissingTerm(config.term.clone()))?;
+        Ok(instantiations
+            .iter()
+            .map(|types| {
+                infer_types(
+                    &self.termenv,
+                    &self.typeenv,
+                    &self.annotation_env,
+                    config,
+                    types,
+                )
+            })
+            .collect())
+    }
+
+    /// As `infer`, but with every selected rule in each map, either typed or
+    /// with why it couldn't be.
+    pub fn try_infer(
+        &self,
+        config: &Config,
+    ) -> Result<Vec<HashMap<RuleId, Result<RuleSemantics, TypeError>>>, Error> {
+        self.try_infer_with(&mut TypeSolver::new(config), config, &None)
+    }
+
+    /// As `try_infer`, but with a solver that can be shared with other calls,
+    /// e.g. to type several terms with one solver process and SMT log, and
+    /// with each rule also checked against `concrete` if given.
+    pub fn try_infer_with(
+        &self,
+        solver: &mut TypeSolver,
+        config: &Config,
+        concrete: &Option<ConcreteTest>,
+    ) -> Result<Vec<HashMap<RuleId, Result<RuleSemantics, TypeError>>>, Error> {
+        let instantiations = self
+            .term_signatures()
+            .get(&config.term)
+            .ok_or_else(|| Error::MissingTerm(config.term.clone()))?;
+        Ok(instantiations
+            .iter()
+            .map(|types| {
+                try_type_rules_with_term_and_types(
+                    solver,
+                    &self.termenv,
+                    &self.typeenv,
+                    &self.annotation_env,
+                    config,
+                    types,
+                    concrete,
+                )
+            })
+            .collect())
+    }
+}
+
+/// Parse the ISLE files together and resolve their types, terms, and
+/// annotations.
+pub fn parse_program(paths: &[PathBuf]) -> Result<Program, Error> {
+    let lexer = Lexer::from_files(paths).map_err(|err| Error::Parse(format!("{:?}", err)))?;
+    let ast = parse(lexer).map_err(|err| Error::Parse(format!("{:?}", err)))?;
+    let mut typeenv = TypeEnv::from_ast(&ast).map_err(|err| Error::Sema(format!("{:?}", err)))?;
+    let termenv = TermEnv::from_ast(&mut typeenv, &ast, false)
+        .map_err(|err| Error::Sema(format!("{:?}", err)))?;
+    let annotation_env = parse_annotations(&ast, &termenv, &typeenv).map_err(Error::Annotation)?;
+    Ok(Program {
+        typeenv,
+        termenv,
+        annotation_env,
+        term_signatures: OnceCell::new(),
+    })
+}
+
+/// Parse the ISLE files and type the rules rooted at `config.term`. There is
+/// one map of typed rules per instantiation of the term, in the order they
+/// are declared, since a rule is typed once for each. To type several terms,
+/// parse once with `parse_program` and call `Program::infer` for each.
+pub fn parse_and_type(
+    paths: &[PathBuf],
+    config: &Config,
+) -> Result<Vec<HashMap<RuleId, RuleSemantics>>, Error> {
+    parse_program(paths)?.infer(config)
+}
+
+// Identifies the build of the meta crate linked into this executable by the
+// executable's size and modification time. Rebuilding the tool for any reason
+// regenerates the prelude too, which is cheap next to using a stale one.
+fn clif_meta_fingerprint() -> Option<String> {
+    let metadata = std::env::current_exe().and_then(std::fs::metadata).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(format!("{} {}", metadata.len(), modified.as_nanos()))
+}
+
+pub fn build_clif_lower_isle(isas: &[Isa]) -> Result<PathBuf, BuildError> {
+    // Build the relevant ISLE prelude using the meta crate. Each ISA set gets
+    // its own directory so switching ISAs doesn't reuse a stale prelude.
+    let mut isa_names: Vec<String> = isas.iter().map(|isa| isa.to_string()).collect();
+    isa_names.sort();
+    isa_names.dedup();
+    let out_dir = format!("veri-isle-clif-gen-{}", isa_names.join("-"));
     let isle_dir = std::path::Path::new(&out_dir);
 
+    // Reuse the cached prelude only if it was generated by this meta crate
+    let fingerprint = clif_meta_fingerprint();
+    let fingerprint_file = isle_dir.join("meta-fingerprint");
     if isle_dir.is_dir() {
         let clif_lower_isle = isle_dir.join("clif_lower.isle");
-        if clif_lower_isle.is_file() {
-            return clif_lower_isle;
+        let cached = std::fs::read_to_string(&fingerprint_file).ok();
+        if clif_lower_isle.is_file()
+            && fingerprint.is_some()
+            && cached.as_deref().map(str::trim) == fingerprint.as_deref()
+        {
+            return Ok(clif_lower_isle);
         }
     }
-    std::fs::create_dir_all(isle_dir)
-        .expect("Could not create directory for CLIF ISLE meta-generated code");
-
-    // For now, build ISLE files for x86 and aarch64
-    let isas = vec![Isa::X86, Isa::Arm64];
+    std::fs::create_dir_all(isle_dir).map_err(|err| BuildError::CreateDir(err.to_string()))?;
 
-    if let Err(err) = cranelift_codegen_meta::generate(&isas, &out_dir, isle_dir.to_str().unwrap())
-    {
-        panic!("Meta generate error: {}", err);
+    cranelift_codegen_meta::generate(isas, &out_dir, isle_dir.to_str().unwrap())
+        .map_err(|err| BuildError::MetaGenerate(err.to_string()))?;
+    if let Some(fingerprint) = fingerprint {
+        std::fs::write(&fingerprint_file, format!("{}\n", fingerprint))
+            .map_err(|err| BuildError::WriteFingerprint(err.to_string()))?;
     }
 
-    PathBuf::from(isle_dir.join("clif_lower.isle"))
+    Ok(PathBuf::from(isle_dir.join("clif_lower.isle")))
 }
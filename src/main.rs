@@ -1,2765 +1,764 @@
 extern crate cranelift_isle;
 
+use clap::builder::RangedU64ValueParser;
 use clap::{ArgAction, Parser};
-use cranelift_isle::lexer::Lexer;
-use cranelift_isle::parser::parse;
+use cranelift_codegen_meta::isa::Isa;
 use cranelift_isle::sema::{self};
-use cranelift_isle::sema::{Pattern, TermEnv, TermId, TypeEnv, VarId};
-use easy_smt::SExprData;
-use easy_smt::{Response, SExpr};
+use cranelift_isle::sema::{TermEnv, TypeEnv};
 use itertools::Itertools;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::hash::Hash;
-use std::path::PathBuf;
-use strum::IntoEnumIterator;
-use strum_macros::{EnumIter, FromRepr};
-use type_inf::annotations::parse_annotations;
+use std::path::{Path, PathBuf};
 use type_inf::annotations::AnnotationEnv;
-use type_inf::build_clif_lower_isle;
-use type_inf::{FLAGS_WIDTH, REG_WIDTH};
-
-use type_inf::termname::pattern_contains_termname;
-use veri_ir::{annotation_ir, ConcreteTest, Expr, TermSignature, Type};
-
-/* ----- STRUCTS FOR RECURSIVE RULE PARSING, TYPE CONVERSION ----- */
-#[derive(Clone, Debug)]
-struct RuleParseTree {
-    // a map of var name to type variable, where var could be
-    // Pattern::Var or var used in Pattern::BindPattern
-    varid_to_type_var_map: HashMap<VarId, u32>,
-    // a map of type var to value, if known
-    type_var_to_val_map: HashMap<u32, i128>,
-    // bookkeeping that tells the next unused type var
-    next_type_var: u32,
-    // combined constraints from all nodes
-    concrete_constraints: HashSet<TypeExpr>,
-    var_constraints: HashSet<TypeExpr>,
-    bv_constraints: HashSet<TypeExpr>,
-
-    ty_vars: HashMap<veri_ir::Expr, u32>,
-    quantified_vars: HashMap<String, u32>,
-    free_vars: HashMap<String, u32>,
-    assumptions: Vec<Expr>,
-    rhs_assertions: Vec<Expr>,
-}
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-// Constraints either assign concrete types to type variables
-// or set them equal to other type variables
-enum TypeExpr {
-    // Symbolic Sum for now. This only checks if the sum of bv widths of the lhs match the rhs.
-    Symbolic(Vec<u32>, Vec<u32>),
-    Concrete(u32, annotation_ir::Type),
-    Variable(u32, u32),
-    // The type variable of the first arg is equal to the value of the second
-    WidthInt(u32, u32),
-}
-
-#[derive(Debug, Clone)]
-pub struct AnnotationTypeInfo {
-    // map of annotation variable to assigned type var
-    pub term: String,
-    pub var_to_type_var: HashMap<String, u32>,
-}
-
-#[derive(Debug)]
-pub struct RuleSemantics {
-    pub annotation_infos: Vec<AnnotationTypeInfo>,
-
-    // map of type var to solved type
-    pub type_var_to_type: HashMap<u32, annotation_ir::Type>,
-
-    pub lhs: veri_ir::Expr,
-    pub rhs: veri_ir::Expr,
-
-    pub quantified_vars: Vec<veri_ir::BoundVar>,
-    pub free_vars: Vec<veri_ir::BoundVar>,
-    pub assumptions: Vec<Expr>,
-    pub rhs_assertions: Vec<Expr>,
-}
-
-#[derive(Clone, Debug)]
-pub struct TypeVarNode {
-    ident: String,
-    construct: TypeVarConstruct,
-    type_var: u32,
-    children: Vec<TypeVarNode>,
-    assertions: Vec<veri_ir::Expr>,
-}
-
-#[derive(Clone, Debug)]
-pub enum TypeVarConstruct {
-    Var,
-    BindPattern,
-    Wildcard(u32),
-    Term(TermId),
-    Const(i128),
-    Let(Vec<String>),
-    And,
-}
-
-#[derive(Parser)]
-#[clap(about, version, author)]
-struct Args {
-    /// Sets the input file
-    #[clap(short, long)]
-    input: Option<String>,
-
-    /// Which LHS root to verify
-    #[clap(short, long, default_value = "lower")]
-    term: String,
-
-    /// Which named rule to verify
-    #[clap(long)]
-    names: Option<Vec<String>>,
-
-    /// Don't use the prelude ISLE files
-    #[clap(short, long, action=ArgAction::SetTrue)]
-    noprelude: bool,
-
-    /// Include the aarch64 files
-    #[clap(short, long, action=ArgAction::SetTrue)]
-    aarch64: bool,
-}
-
-pub struct Config {
-    /// Which LHS root to verify
-    pub term: String,
-    /// Which named rule to verify
-    pub names: Option<Vec<String>>,
-}
-
-/* ----- CONVERT AST TO RULE SEMANTICS ----- */
-
-fn convert_type(aty: &annotation_ir::Type) -> veri_ir::Type {
-    match aty {
-        annotation_ir::Type::BitVectorUnknown(..) => veri_ir::Type::BitVector(None),
-        annotation_ir::Type::BitVector => veri_ir::Type::BitVector(None),
-        annotation_ir::Type::BitVectorWithWidth(w) => veri_ir::Type::BitVector(Some(*w)),
-        annotation_ir::Type::Int => veri_ir::Type::Int,
-        annotation_ir::Type::Bool => veri_ir::Type::Bool,
-        annotation_ir::Type::Poly(_) => veri_ir::Type::BitVector(None),
-    }
-}
-
-fn type_to_num(aty: &annotation_ir::Type) -> String {
-    match aty {
-        annotation_ir::Type::BitVectorUnknown(..) => "bvunk".to_string(),
-        annotation_ir::Type::BitVector => "bv".to_string(),
-        annotation_ir::Type::BitVectorWithWidth(w) => format!("bv{}", &w),
-        annotation_ir::Type::Int => "int".to_string(),
-        annotation_ir::Type::Bool => "bool".to_string(),
-        annotation_ir::Type::Poly(_) => "poly".to_string(),
-    }
-}
-
-fn annotation_type_for_vir_type(ty: &Type) -> annotation_ir::Type {
-    match ty {
-        Type::BitVector(Some(x)) => annotation_ir::Type::BitVectorWithWidth(*x),
-        Type::BitVector(None) => annotation_ir::Type::BitVector,
-        Type::Bool => annotation_ir::Type::Bool,
-        Type::Int => annotation_ir::Type::Int,
-    }
-}
-
-pub fn type_rules_with_term_and_types(
-    termenv: &TermEnv,
-    typeenv: &TypeEnv,
-    annotation_env: &AnnotationEnv,
-    config: &Config,
-    types: &TermSignature,
-    concrete: &Option<ConcreteTest>,
-) -> HashMap<sema::RuleId, RuleSemantics> {
-    let mut solutions = HashMap::new();
-
-    for rule in &termenv.rules {
-        // Only type rules with the given term on the LHS
-        if !pattern_contains_termname(
-            // Hack for now: typeid not used
-            &Pattern::Term(
-                cranelift_isle::sema::TypeId(0),
-                rule.root_term,
-                rule.args.clone(),
-            ),
-            &config.term,
-            termenv,
-            typeenv,
-        ) {
-            continue;
-        }
-        if let Some(names) = &config.names {
-            if rule.name.is_none() {
-                continue;
-            }
-            let name = &typeenv.syms[rule.name.unwrap().index()];
-            if !names.contains(name) {
-                continue;
+use type_inf::concrete::parse_concrete_test;
+use type_inf::inference::{
+    annotation_coverage, rule_name, type_to_num, Config, RuleSemantics, RuleStats, SolverKind,
+    TypeSolver,
+};
+use type_inf::report::{Reporter, Verbosity};
+use type_inf::{build_clif_lower_isle, parse_program, Program, TargetConfig};
+use veri_ir::TermSignature;
+
+/* ----- OUTPUT FILES ----- */
+
+// A file name for one rule's output, e.g. `graph.lower.0.my_rule.dot` for
+// `graph.dot`, so rules, terms, and instantiations don't overwrite each other.
+fn per_rule_file_name(path: &str, term: &str, instantiation: usize, rule: &str) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let rule: String = rule
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
             }
-        }
-        if let Some(s) = type_annotations_using_rule(
-            rule,
-            annotation_env,
-            typeenv,
-            termenv,
-            &config.term,
-            &types,
-            concrete,
-        ) {
-            // // Uncomment for debugging
-            // for a in &s.annotation_infos {
-            //     println!("{}", a.term);
-            //     for (var, type_var) in &a.var_to_type_var {
-            //         println!("{}: {:#?}", var, s.type_var_to_type[type_var]);
-            //     }
-            //     println!();
-            // }
-            solutions.insert(rule.id, s);
-        }
+        })
+        .collect();
+    let mut name = format!("{}.{}.{}.{}", stem, term, instantiation, rule);
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
     }
-    solutions
+    name
 }
 
-fn type_annotations_using_rule<'a>(
-    rule: &'a sema::Rule,
-    annotation_env: &'a AnnotationEnv,
-    typeenv: &'a TypeEnv,
-    termenv: &'a TermEnv,
-    term: &String,
-    types: &TermSignature,
-    _concrete: &'a Option<ConcreteTest>,
-) -> Option<RuleSemantics> {
-    let mut parse_tree = RuleParseTree {
-        varid_to_type_var_map: HashMap::new(),
-        type_var_to_val_map: HashMap::new(),
-        next_type_var: 1,
-        concrete_constraints: HashSet::new(),
-        var_constraints: HashSet::new(),
-        bv_constraints: HashSet::new(),
-        ty_vars: HashMap::new(),
-        quantified_vars: HashMap::new(),
-        free_vars: HashMap::new(),
-        assumptions: vec![],
-        rhs_assertions: vec![],
-    };
-    let mut annotation_infos = vec![];
-    if !rule.iflets.is_empty() {
-        print!("\n\tif-lets:");
-        for iflet in &rule.iflets {
-            let iflet_lhs = &mut create_parse_tree_pattern(
-                rule,
-                &iflet.lhs,
-                &mut parse_tree,
-                typeenv,
-                termenv,
-                term,
-                types,
-            );
-            let iflet_rhs =
-                &mut create_parse_tree_expr(rule, &iflet.rhs, &mut parse_tree, typeenv, termenv);
-
-            let iflet_lhs_expr = add_rule_constraints(
-                &mut parse_tree,
-                iflet_lhs,
-                termenv,
-                typeenv,
-                annotation_env,
-                &mut annotation_infos,
-                false,
-            );
-            if iflet_lhs_expr.is_none() {
-                return None;
-            }
-
-            let iflet_rhs_expr = add_rule_constraints(
-                &mut parse_tree,
-                iflet_rhs,
-                termenv,
-                typeenv,
-                annotation_env,
-                &mut annotation_infos,
-                false,
-            );
-            if iflet_rhs_expr.is_none() {
-                return None;
-            }
-            parse_tree
-                .var_constraints
-                .insert(TypeExpr::Variable(iflet_lhs.type_var, iflet_rhs.type_var));
-            parse_tree.assumptions.push(veri_ir::Expr::Binary(
-                veri_ir::BinaryOp::Eq,
-                Box::new(iflet_lhs_expr.unwrap()),
-                Box::new(iflet_rhs_expr.unwrap()),
-            ));
-        }
-        print!("\n");
-    }
-
-    let lhs = &mut create_parse_tree_pattern(
-        rule,
-        // Hack for now: typeid not used
-        &sema::Pattern::Term(
-            cranelift_isle::sema::TypeId(0),
-            rule.root_term,
-            rule.args.clone(),
-        ),
-        &mut parse_tree,
-        typeenv,
-        termenv,
-        term,
-        types,
+/* ----- RULE STATISTICS ----- */
+
+// One row per typed rule, slowest to solve first.
+fn print_stats(stats: &[(String, RuleStats)]) {
+    let width = stats
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["rule".len()])
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:<width$}  {:>9}  {:>8}  {:>8}  {:>8}  {:>10}",
+        "rule", "type vars", "concrete", "var", "bv", "solve (ms)"
     );
-    let rhs = &mut create_parse_tree_expr(rule, &rule.rhs, &mut parse_tree, typeenv, termenv);
-
-    println!("Typing rule:");
-    print!("\tLHS:");
-    let lhs_expr = add_rule_constraints(
-        &mut parse_tree,
-        lhs,
-        termenv,
-        typeenv,
-        annotation_env,
-        &mut annotation_infos,
-        false,
-    );
-    if lhs_expr.is_none() {
-        return None;
-    }
-    print!("\n\tRHS:");
-    let rhs_expr = add_rule_constraints(
-        &mut parse_tree,
-        rhs,
-        termenv,
-        typeenv,
-        annotation_env,
-        &mut annotation_infos,
-        true,
-    );
-    if rhs_expr.is_none() {
-        return None;
+    for (name, s) in stats
+        .iter()
+        .sorted_by_key(|(_, s)| std::cmp::Reverse(s.solve_time))
+    {
+        println!(
+            "{:<width$}  {:>9}  {:>8}  {:>8}  {:>8}  {:>10.3}",
+            name,
+            s.type_vars,
+            s.concrete_constraints,
+            s.var_constraints,
+            s.bv_constraints,
+            s.solve_time.as_secs_f64() * 1000.0
+        );
     }
-    println!();
-
-    match (lhs_expr, rhs_expr) {
-        (Some(lhs_expr), Some(rhs_expr)) => {
-            parse_tree
-                .var_constraints
-                .insert(TypeExpr::Variable(lhs.type_var, rhs.type_var));
-
-            // NOTE: This is where SMT Solver should be called
-            let (solution, _bv_unknown_width_sets) = solve_constraints(
-                &parse_tree.concrete_constraints,
-                &parse_tree.var_constraints,
-                &parse_tree.bv_constraints,
-                &mut parse_tree.type_var_to_val_map,
-                &lhs_expr,
-                &rhs_expr,
-                // Some(&parse_tree.ty_vars),
-            );
-
-            // Print here?
-            let smt = easy_smt::ContextBuilder::new()
-                .replay_file(Some(std::fs::File::create("type_solver.smt2").unwrap()))
-                .solver("z3", ["-smt2", "-in"])
-                .build()
-                .unwrap();
-
-            let mut solver = TypeSolver::new(smt);
-            let lhs = solver.display_isle_pattern(
-                termenv,
-                typeenv,
-                rule,
-                &annotation_infos,
-                &solution,
-                &Pattern::Term(
-                    cranelift_isle::sema::TypeId(0),
-                    rule.root_term,
-                    rule.args.clone(),
-                ),
-                None,
-            );
-            println!("{}", solver.smt.display(lhs));
-
-            println!("=>");
-            let rhs = solver.display_isle_expr(
-                termenv,
-                typeenv,
-                rule,
-                &annotation_infos,
-                &solution,
-                &rule.rhs,
-                None,
-            );
-            println!("{}", solver.smt.display(rhs));
+}
 
-            let mut tymap = HashMap::new();
+/* ----- CSV PROJECTION OF RULE SEMANTICS ----- */
 
-            for (expr, t) in &parse_tree.ty_vars {
-                if let Some(ty) = solution.get(&t) {
-                    tymap.insert(*t, convert_type(ty));
-                } else {
-                    panic!("missing type variable {} in solution for: {:?}", t, expr);
-                }
-            }
-            let mut quantified_vars = vec![];
-            for (s, t) in parse_tree.quantified_vars.iter().sorted() {
-                let expr = veri_ir::Expr::Terminal(veri_ir::Terminal::Var(s.clone()));
-                if let Some(ty) = solution.get(t) {
-                    let ty = convert_type(ty);
-                    parse_tree.ty_vars.insert(expr, *t);
-                    tymap.insert(*t, ty.clone());
-                    quantified_vars.push(veri_ir::BoundVar {
-                        name: s.clone(),
-                        tyvar: *t,
-                    });
-                } else {
-                    panic!("missing type variable {} in solution for: {:?}", t, expr);
-                }
-            }
-            let mut free_vars = vec![];
-            for (s, t) in parse_tree.free_vars {
-                let expr = veri_ir::Expr::Terminal(veri_ir::Terminal::Var(s.clone()));
-                if let Some(ty) = solution.get(&t) {
-                    let ty = convert_type(ty);
-                    parse_tree.ty_vars.insert(expr, t);
-                    tymap.insert(t, ty.clone());
-                    free_vars.push(veri_ir::BoundVar { name: s, tyvar: t });
-                } else {
-                    panic!("missing type variable {} in solution for: {:?}", t, expr);
-                }
-            }
-
-            Some(RuleSemantics {
-                annotation_infos,
-                type_var_to_type: solution,
-                lhs: lhs_expr,
-                rhs: rhs_expr,
-                quantified_vars,
-                free_vars,
-                assumptions: parse_tree.assumptions,
-                rhs_assertions: parse_tree.rhs_assertions,
-            })
-        }
-        _ => None,
+// Quote a CSV field if it has a separator, quote, or line break in it.
+fn csv_field(field: &str) -> Cow<str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
     }
 }
 
-// Recursive process tree
-fn create_parse_tree_pattern(
-    rule: &sema::Rule,
-    pattern: &sema::Pattern,
-    tree: &mut RuleParseTree,
-    typeenv: &TypeEnv,
+// One row per annotation variable of each typed rule, with the term the
+// annotation belongs to, in rule order.
+fn csv_rows(
     termenv: &TermEnv,
-    term: &String,
-    types: &TermSignature,
-) -> TypeVarNode {
-    match pattern {
-        sema::Pattern::Term(_, term_id, args) => {
-            let sym = termenv.terms[term_id.index()].name;
-            let name = typeenv.syms[sym.index()].clone();
-
-            // process children first
-            let mut children = vec![];
-            for (i, arg) in args.iter().enumerate() {
-                let child =
-                    create_parse_tree_pattern(rule, arg, tree, typeenv, termenv, term, types);
-
-                // Our specified input term, use external types
-                if name.eq(term) {
-                    tree.concrete_constraints.insert(TypeExpr::Concrete(
-                        child.type_var,
-                        annotation_type_for_vir_type(&types.args[i]),
-                    ));
-                }
-                children.push(child);
-            }
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            if name.eq(term) {
-                tree.concrete_constraints.insert(TypeExpr::Concrete(
-                    type_var,
-                    annotation_type_for_vir_type(&types.ret),
+    typeenv: &TypeEnv,
+    solutions: &HashMap<sema::RuleId, RuleSemantics>,
+) -> String {
+    let mut out = String::new();
+    for (id, semantics) in solutions.iter().sorted_by_key(|(id, _)| id.index()) {
+        let rule = rule_name(&termenv.rules[id.index()], typeenv);
+        for info in &semantics.annotation_infos {
+            for (var, t) in info.var_to_type_var.iter().sorted() {
+                let ty = match semantics.type_var_to_type.get(t) {
+                    Some(ty) => type_to_num(ty),
+                    None => "?".to_string(),
+                };
+                out.push_str(&format!(
+                    "{},{},{},{}\n",
+                    csv_field(&rule),
+                    csv_field(&info.term),
+                    csv_field(var),
+                    csv_field(&ty)
                 ));
             }
-
-            TypeVarNode {
-                ident: format!("{}__{}", name, type_var),
-                construct: TypeVarConstruct::Term(term_id.clone()),
-                type_var,
-                children,
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::Var(_, var_id) => {
-            let sym = rule.vars[var_id.index()].name;
-            let ident = typeenv.syms[sym.index()].clone();
-
-            let type_var = tree
-                .varid_to_type_var_map
-                .entry(*var_id)
-                .or_insert(tree.next_type_var);
-            if *type_var == tree.next_type_var {
-                tree.next_type_var += 1;
-            }
-            let ident = format!("{}__clif{}__{}", ident, var_id.index(), *type_var);
-            // this is a base case so there are no children
-            TypeVarNode {
-                ident,
-                construct: TypeVarConstruct::Var,
-                type_var: *type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::BindPattern(_, var_id, subpat) => {
-            let sym = rule.vars[var_id.index()].name;
-
-            let type_var = *tree
-                .varid_to_type_var_map
-                .entry(*var_id)
-                .or_insert(tree.next_type_var);
-            if type_var == tree.next_type_var {
-                tree.next_type_var += 1;
-            }
-
-            let ident = format!(
-                "{}__clif{}__{}",
-                typeenv.syms[sym.index()],
-                var_id.index(),
-                type_var
-            );
-
-            // this is a base case so there are no children
-            let var_node = TypeVarNode {
-                ident: ident.clone(),
-                construct: TypeVarConstruct::Var,
-                type_var: type_var,
-                children: vec![],
-                assertions: vec![],
-            };
-
-            let subpat_node =
-                create_parse_tree_pattern(rule, subpat, tree, typeenv, termenv, term, types);
-
-            let bind_type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.var_constraints
-                .insert(TypeExpr::Variable(type_var, subpat_node.type_var));
-            tree.var_constraints
-                .insert(TypeExpr::Variable(bind_type_var, type_var));
-            tree.var_constraints
-                .insert(TypeExpr::Variable(bind_type_var, subpat_node.type_var));
-
-            TypeVarNode {
-                ident,
-                construct: TypeVarConstruct::BindPattern,
-                type_var: type_var,
-                children: vec![var_node, subpat_node],
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::Wildcard(_) => {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-            TypeVarNode {
-                ident: format!("wildcard__{}", type_var),
-                construct: TypeVarConstruct::Wildcard(type_var),
-                type_var: type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::ConstPrim(_, sym) => {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-            let name = typeenv.syms[sym.index()].clone();
-            let val = match name.as_str() {
-                "I64" => 64,
-                "I32" => 32,
-                "I16" => 16,
-                "I8" => 8,
-                "true" => 1,
-                "false" => 0,
-                // Not currently used, but parsed
-                "I128" => 16,
-                _ => todo!("{:?}", &name),
-            };
-            let name = format!("{}__{}", name, type_var);
-
-            TypeVarNode {
-                ident: name,
-                construct: TypeVarConstruct::Const(val),
-                type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::ConstInt(_, num) => {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-            let name = format!("{}__{}", num, type_var);
-            TypeVarNode {
-                ident: name,
-                construct: TypeVarConstruct::Const(*num),
-                type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Pattern::And(_, subpats) => {
-            let mut children = vec![];
-            let mut ty_vars = vec![];
-            for p in subpats {
-                let child = create_parse_tree_pattern(rule, p, tree, typeenv, termenv, term, types);
-                ty_vars.push(child.type_var);
-                children.push(child);
-            }
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            // Assert all sub type constraints are equivalent to the first subexpression
-            let first = ty_vars[0];
-            for e in &ty_vars[1..] {
-                tree.var_constraints
-                    .insert(TypeExpr::Variable(first, e.to_owned()));
-            }
-
-            TypeVarNode {
-                ident: String::from("and"),
-                construct: TypeVarConstruct::And,
-                type_var,
-                children,
-                assertions: vec![],
-            }
         }
     }
+    out
 }
 
-fn create_parse_tree_expr(
-    rule: &sema::Rule,
-    expr: &sema::Expr,
-    tree: &mut RuleParseTree,
-    typeenv: &TypeEnv,
-    termenv: &TermEnv,
-) -> TypeVarNode {
-    match expr {
-        sema::Expr::Term(_, term_id, args) => {
-            let sym = termenv.terms[term_id.index()].name;
-            let name = typeenv.syms[sym.index()].clone();
-
-            // process children first
-            let mut children = vec![];
-            for arg in args {
-                let child = create_parse_tree_expr(rule, arg, tree, typeenv, termenv);
-                children.push(child);
-            }
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            TypeVarNode {
-                ident: format!("{}__{}", name, type_var),
-                construct: TypeVarConstruct::Term(term_id.clone()),
-                type_var,
-                children,
-                assertions: vec![],
-            }
-        }
-        sema::Expr::Var(_, var_id) => {
-            let mut ident = var_id.0.to_string();
-            if var_id.index() < rule.vars.len() {
-                let sym = rule.vars[var_id.index()].name;
-                ident = typeenv.syms[sym.index()].clone();
-            } else {
-                println!("var {} not found, using var id instead", var_id.0);
-                ident = format!("v{}", ident);
-            }
+/* ----- JSON PROJECTION OF RULE SEMANTICS ----- */
 
-            let type_var = tree
-                .varid_to_type_var_map
-                .entry(*var_id)
-                .or_insert(tree.next_type_var);
-            if *type_var == tree.next_type_var {
-                tree.next_type_var += 1;
-            }
-            let ident = format!("{}__clif{}__{}", ident, var_id.index(), *type_var);
-            // this is a base case so there are no children
-            TypeVarNode {
-                ident,
-                construct: TypeVarConstruct::Var,
-                type_var: *type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Expr::ConstPrim(_, sym) => {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-            let name = typeenv.syms[sym.index()].clone();
-            let val = match name.as_str() {
-                "I8" => 8,
-                "I64" => 64,
-                "I32" => 32,
-                "false" => 0,
-                "true" => 1,
-                _ => todo!("{:?}", &name),
-            };
-            let name = format!("{}__{}", name, type_var);
-            TypeVarNode {
-                ident: name,
-                construct: TypeVarConstruct::Const(val),
-                type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Expr::ConstInt(_, num) => {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-            let name = format!("{}__{}", num, type_var);
-            TypeVarNode {
-                ident: name,
-                construct: TypeVarConstruct::Const(*num),
-                type_var,
-                children: vec![],
-                assertions: vec![],
-            }
-        }
-        sema::Expr::Let { bindings, body, .. } => {
-            let mut children = vec![];
-            let mut bound = vec![];
-            for (varid, _, expr) in bindings {
-                let sym = rule.vars[varid.index()].name;
-                let var = typeenv.syms[sym.index()].clone();
-                let subpat_node = create_parse_tree_expr(rule, expr, tree, typeenv, termenv);
-
-                let ty_var = tree.next_type_var;
-                tree.next_type_var += 1;
-
-                tree.var_constraints
-                    .insert(TypeExpr::Variable(ty_var, subpat_node.type_var));
-
-                tree.varid_to_type_var_map.insert(*varid, ty_var);
-                children.push(subpat_node);
-                let ident = format!("{}__clif{}__{}", var, varid.index(), ty_var);
-                tree.quantified_vars.insert(ident.clone(), ty_var);
-                bound.push(ident);
-            }
-            let body = create_parse_tree_expr(rule, body, tree, typeenv, termenv);
-            let body_var = body.type_var;
-            children.push(body);
-
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
+// veri_ir expressions and types aren't serializable, so the JSON output is a
+// projection with every type variable replaced by its solved type.
+#[derive(Serialize)]
+struct InstantiationJson {
+    term: String,
+    signature: String,
+    rules: Vec<RuleJson>,
+}
 
-            let name = format!("let__{}", type_var);
+#[derive(Serialize)]
+struct RuleJson {
+    rule: String,
+    terms: Vec<TermJson>,
+    quantified_vars: BTreeMap<String, String>,
+    free_vars: BTreeMap<String, String>,
+    lhs: String,
+    rhs: String,
+}
 
-            // The let should have the same type as the body
-            tree.var_constraints
-                .insert(TypeExpr::Variable(type_var, body_var));
+#[derive(Serialize)]
+struct TermJson {
+    term: String,
+    vars: BTreeMap<String, String>,
+}
 
-            TypeVarNode {
-                ident: name,
-                construct: TypeVarConstruct::Let(bound),
-                type_var,
-                children,
-                assertions: vec![],
-            }
+impl InstantiationJson {
+    fn new(
+        termenv: &TermEnv,
+        typeenv: &TypeEnv,
+        config: &Config,
+        types: &TermSignature,
+        solutions: &HashMap<sema::RuleId, RuleSemantics>,
+    ) -> Self {
+        let rules = solutions
+            .iter()
+            .sorted_by_key(|(id, _)| id.index())
+            .map(|(id, s)| RuleJson::new(&termenv.rules[id.index()], typeenv, s))
+            .collect();
+        Self {
+            term: config.term.clone(),
+            signature: format!("{:?}", types),
+            rules,
         }
     }
 }
 
-fn const_fold_to_int(e: &veri_ir::Expr) -> Option<i128> {
-    match e {
-        Expr::Terminal(veri_ir::Terminal::Const(c, _)) => Some(*c),
-        _ => None,
+impl RuleJson {
+    fn new(rule: &sema::Rule, typeenv: &TypeEnv, semantics: &RuleSemantics) -> Self {
+        let type_of = |t: &u32| match semantics.type_var_to_type.get(t) {
+            Some(ty) => type_to_num(ty),
+            None => "?".to_string(),
+        };
+        let bound_vars = |vars: &Vec<veri_ir::BoundVar>| {
+            vars.iter()
+                .map(|v| (v.name.clone(), type_of(&v.tyvar)))
+                .collect()
+        };
+        Self {
+            rule: rule_name(rule, typeenv),
+            terms: semantics
+                .annotation_infos
+                .iter()
+                .map(|a| TermJson {
+                    term: a.term.clone(),
+                    vars: a
+                        .var_to_type_var
+                        .iter()
+                        .map(|(v, t)| (v.clone(), type_of(t)))
+                        .collect(),
+                })
+                .collect(),
+            quantified_vars: bound_vars(&semantics.quantified_vars),
+            free_vars: bound_vars(&semantics.free_vars),
+            lhs: format!("{:?}", semantics.lhs),
+            rhs: format!("{:?}", semantics.rhs),
+        }
     }
 }
 
-fn add_annotation_constraints(
-    expr: annotation_ir::Expr,
-    tree: &mut RuleParseTree,
-    annotation_info: &mut AnnotationTypeInfo,
-) -> (veri_ir::Expr, u32) {
-    let (e, t) = match expr {
-        annotation_ir::Expr::Var(x, ..) => {
-            let mut t = tree.next_type_var;
-            if annotation_info.var_to_type_var.contains_key(&x) {
-                t = annotation_info.var_to_type_var[&x];
-            } else {
-                annotation_info.var_to_type_var.insert(x.clone(), t);
-                tree.next_type_var += 1;
-            }
-            let name = format!("{}__{}__{}", annotation_info.term, x, t);
-
-            // Support the introduction of extra variables in the specification.
-            //
-            // TODO(mbm): understand whether this needs to be in quantified, free or both?
-            tree.quantified_vars.insert(name.clone(), t);
-            tree.free_vars.insert(name.clone(), t);
-
-            (veri_ir::Expr::Terminal(veri_ir::Terminal::Var(name)), t)
-        }
-        annotation_ir::Expr::Const(c, ..) => {
-            let t = tree.next_type_var;
-            let e = veri_ir::Expr::Terminal(veri_ir::Terminal::Const(c.value, t));
-            match c.ty {
-                annotation_ir::Type::BitVector => {
-                    let ty = annotation_ir::Type::BitVectorWithWidth(c.width);
-                    tree.concrete_constraints.insert(TypeExpr::Concrete(t, ty));
-                }
-                _ => {
-                    tree.concrete_constraints
-                        .insert(TypeExpr::Concrete(t, c.ty.clone()));
-                }
-            }
-            tree.next_type_var += 1;
-
-            // If constant is known, add the value to the tree. Useful for
-            // capturing isleTypes
-            tree.type_var_to_val_map.insert(t, c.value);
-            (e, t)
-        }
-        annotation_ir::Expr::True => {
-            let t = tree.next_type_var;
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::Terminal(veri_ir::Terminal::True), t)
-        }
-        annotation_ir::Expr::False => {
-            let t = tree.next_type_var;
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::Terminal(veri_ir::Terminal::False), t)
-        }
-
-        annotation_ir::Expr::WidthOf(x) => {
-            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(tx, annotation_ir::Type::BitVector));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Int));
-            tree.concrete_constraints.insert(TypeExpr::WidthInt(tx, t));
-            (veri_ir::Expr::WidthOf(Box::new(ex)), t)
-        }
-
-        annotation_ir::Expr::Eq(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::Eq, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::Imp(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::Imp, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::Lte(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::Lte, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::Not(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::Unary(veri_ir::UnaryOp::Not, Box::new(e1)), t)
-        }
-        annotation_ir::Expr::Or(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::Or, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::And(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::Bool));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::And, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVSgt(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSgt, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVSgte(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSgte, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVSlt(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSlt, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVSlte(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSlte, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVUgt(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUgt, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVUgte(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUgte, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVUlt(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUlt, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVUlte(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUlte, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVSaddo(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSaddo, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVNeg(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Unary(veri_ir::UnaryOp::BVNeg, Box::new(e1)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVNot(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Unary(veri_ir::UnaryOp::BVNot, Box::new(e1)),
-                t,
-            )
-        }
-
-        annotation_ir::Expr::BVMul(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVMul, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVUDiv(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUDiv, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVSDiv(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSDiv, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVAdd(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVAdd, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVSub(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSub, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVUrem(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVUrem, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVSrem(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVSrem, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
+#[derive(Parser)]
+#[clap(about, version, author)]
+struct Args {
+    /// Sets the input files
+    #[clap(short, long)]
+    input: Vec<String>,
 
-        annotation_ir::Expr::BVAnd(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVAnd, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVOr(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVOr, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVXor(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVXor, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVRotl(x, a) => {
-            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info);
-            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
-
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVRotl, Box::new(xe), Box::new(ae)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVRotr(x, a) => {
-            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info);
-            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
-
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVRotr, Box::new(xe), Box::new(ae)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVShl(x, a) => {
-            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info);
-            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
-            tree.var_constraints.insert(TypeExpr::Variable(xt, at));
-
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVShl, Box::new(xe), Box::new(ae)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVShr(x, a) => {
-            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info);
-            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
-            tree.var_constraints.insert(TypeExpr::Variable(xt, at));
-
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVShr, Box::new(xe), Box::new(ae)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVAShr(x, a) => {
-            let (xe, xt) = add_annotation_constraints(*x, tree, annotation_info);
-            let (ae, at) = add_annotation_constraints(*a, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(at, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, xt));
-            tree.var_constraints.insert(TypeExpr::Variable(at, xt));
-
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::BVAShr, Box::new(xe), Box::new(ae)),
-                t,
-            )
-        }
-        annotation_ir::Expr::Lt(x, y) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Binary(veri_ir::BinaryOp::Lt, Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
+    /// Which LHS roots to verify
+    #[clap(short, long, default_values = ["lower"])]
+    term: Vec<String>,
 
-        annotation_ir::Expr::BVConvTo(w, x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
+    /// Type the rules rooted at every term with instantiations, in name order,
+    /// instead of the --term roots
+    #[clap(long, action=ArgAction::SetTrue, conflicts_with = "term")]
+    all_terms: bool,
 
-            let width = match *w {
-                annotation_ir::Width::Const(x) => x,
-                annotation_ir::Width::RegWidth => REG_WIDTH,
-            };
+    /// Which named rule to verify
+    #[clap(long)]
+    names: Option<Vec<String>>,
 
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(width),
-            ));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    /// Interpret each --names entry as a regex over the whole rule name
+    #[clap(long, action=ArgAction::SetTrue)]
+    names_regex: bool,
 
-            (veri_ir::Expr::BVConvTo(Box::new(e1)), t)
-        }
-        annotation_ir::Expr::BVConvToVarWidth(w, x) => {
-            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            // In the dynamic case, we don't know the width at this point
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
-
-            if let Some(w) = const_fold_to_int(&we) {
-                tree.concrete_constraints.insert(TypeExpr::Concrete(
-                    t,
-                    annotation_ir::Type::BitVectorWithWidth(w.try_into().unwrap()),
-                ));
-                tree.bv_constraints
-                    .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    /// Which named rules to skip
+    #[clap(long)]
+    exclude_names: Vec<String>,
 
-                (veri_ir::Expr::BVConvTo(Box::new(e1)), t)
-            } else {
-                tree.concrete_constraints.insert(TypeExpr::WidthInt(t, wt));
-                tree.bv_constraints
-                    .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-                tree.bv_constraints
-                    .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-
-                (
-                    veri_ir::Expr::BVConvToVarWidth(Box::new(we), Box::new(e1)),
-                    t,
-                )
-            }
-        }
-        annotation_ir::Expr::BVSignExtToVarWidth(w, x) => {
-            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            // In the dynamic case, we don't know the width at this point
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-
-            (
-                veri_ir::Expr::BVSignExtToVarWidth(Box::new(we), Box::new(e1)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVZeroExtTo(w, x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            let width = match *w {
-                veri_ir::annotation_ir::Width::Const(c) => c,
-                veri_ir::annotation_ir::Width::RegWidth => REG_WIDTH,
-            };
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(width),
-            ));
+    /// Also read annotations from this file. It may only annotate terms and
+    /// types the inputs declare, and only give specs to terms without one;
+    /// its instantiations replace any in the inputs for the same term.
+    #[clap(long)]
+    annotations: Vec<String>,
 
-            (veri_ir::Expr::BVZeroExtTo(width, Box::new(e1)), t)
-        }
-        annotation_ir::Expr::BVZeroExtToVarWidth(w, x) => {
-            let (we, wt) = add_annotation_constraints(*w, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            // In the dynamic case, we don't know the width at this point
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(wt, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-
-            (
-                veri_ir::Expr::BVZeroExtToVarWidth(Box::new(we), Box::new(e1)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVSignExtTo(w, x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            let width = match *w {
-                veri_ir::annotation_ir::Width::Const(c) => c,
-                veri_ir::annotation_ir::Width::RegWidth => REG_WIDTH,
-            };
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(width),
-            ));
+    /// Don't use the prelude ISLE files
+    #[clap(short, long, action=ArgAction::SetTrue)]
+    noprelude: bool,
 
-            tree.next_type_var += 1;
+    /// Include the aarch64 files; shorthand for `--arch aarch64`
+    #[clap(short, long, action=ArgAction::SetTrue)]
+    aarch64: bool,
 
-            (veri_ir::Expr::BVSignExtTo(width, Box::new(e1)), t)
-        }
-        annotation_ir::Expr::BVExtract(l, r, x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(l - r + 1),
-            ));
+    /// Include the ISLE files for this architecture from `ref/<arch>`, and
+    /// generate the CLIF prelude for it. Only `aarch64` is checked in
+    #[clap(long)]
+    arch: Vec<String>,
 
-            tree.next_type_var += 1;
+    /// Width of a general-purpose register on the target
+    #[clap(long, default_value_t = TargetConfig::default().reg_width)]
+    reg_width: usize,
 
-            (veri_ir::Expr::BVExtract(l, r, Box::new(e1)), t)
-        }
-        annotation_ir::Expr::BVConcat(xs) => {
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
+    /// Width of the flags appended by flag-setting instructions on the target
+    #[clap(long, default_value_t = TargetConfig::default().flags_width)]
+    flags_width: usize,
 
-            let mut sum_bvs = vec![];
+    /// Which ISAs to generate the CLIF prelude for
+    #[clap(long, default_values = ["x86_64", "aarch64"])]
+    isa: Vec<String>,
 
-            let mut exprs = vec![];
-            for x in xs {
-                let (xe, xt) = add_annotation_constraints(x, tree, annotation_info);
-                tree.bv_constraints
-                    .insert(TypeExpr::Concrete(xt, annotation_ir::Type::BitVector));
+    /// Where to write the SMT replay log
+    #[clap(long, default_value = "type_solver.smt2")]
+    smt_log: String,
 
-                // add each bv to the sum_bv
-                sum_bvs.push(xt);
+    /// Don't write an SMT replay log
+    #[clap(long, action=ArgAction::SetTrue)]
+    no_smt_log: bool,
 
-                exprs.push(xe);
-            }
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.concrete_constraints
-                .insert(TypeExpr::Symbolic(sum_bvs, vec![t]));
-            tree.next_type_var += 1;
+    /// Which SMT solver to use
+    #[clap(long, value_enum, default_value_t = SolverKind::Z3)]
+    solver: SolverKind,
 
-            (veri_ir::Expr::BVConcat(exprs), t)
-        }
-        annotation_ir::Expr::BVIntToBv(w, x) => {
-            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info);
+    /// Type rules in parallel
+    #[clap(long, action=ArgAction::SetTrue)]
+    parallel: bool,
 
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
+    /// Print only the typed rules
+    #[clap(short, long, action=ArgAction::SetTrue, conflicts_with = "verbose")]
+    quiet: bool,
 
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(tx, annotation_ir::Type::Int));
+    /// Also trace each term as its rule is typed
+    #[clap(short, long, action=ArgAction::SetTrue)]
+    verbose: bool,
 
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(w),
-            ));
+    /// Print only the rules that couldn't be typed, with why, and exit with
+    /// an error if there are any
+    #[clap(long, action=ArgAction::SetTrue, conflicts_with_all = ["quiet", "verbose"])]
+    only_failing: bool,
 
-            (veri_ir::Expr::BVIntToBV(w, Box::new(ex)), t)
-        }
-        annotation_ir::Expr::BVToInt(x) => {
-            let (ex, tx) = add_annotation_constraints(*x.clone(), tree, annotation_info);
+    /// Solver time limit per rule, in milliseconds
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// Report rules whose types aren't uniquely determined
+    #[clap(long, action=ArgAction::SetTrue)]
+    check_unique: bool,
+
+    /// Print each rule's type constraints before solving them
+    #[clap(long, action=ArgAction::SetTrue)]
+    dump_constraints: bool,
+
+    /// Treat unannotated terms with one argument of their return type as the
+    /// identity instead of skipping their rules
+    #[clap(long, action=ArgAction::SetTrue)]
+    assume_identity: bool,
+
+    /// Let a shift or rotate amount be narrower than the value it moves
+    #[clap(long, action=ArgAction::SetTrue)]
+    narrow_shift_amounts: bool,
+
+    /// Skip rules with a bitvector wider than this among their inferred
+    /// types, which usually means they're underconstrained
+    #[clap(long, default_value_t = 512)]
+    max_width: usize,
+
+    /// Treat unannotated terms without arguments as unknown values typed by
+    /// their uses instead of skipping their rules
+    #[clap(long, action=ArgAction::SetTrue)]
+    poly_nullary: bool,
+
+    /// Print each typed rule's type variable and constraint counts and solve
+    /// time, slowest first
+    #[clap(long, action=ArgAction::SetTrue)]
+    stats: bool,
+
+    /// Skip rules with a `conv_to` that narrows its operand
+    #[clap(long, action=ArgAction::SetTrue)]
+    strict_conv_to: bool,
+
+    /// Skip rules with a switch on a bitvector at most this wide, from 1 to 16
+    /// bits, that doesn't match every value
+    #[clap(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..=16))]
+    switch_coverage: Option<usize>,
+
+    /// Explain how the type variables with this name, or this `tN` type
+    /// variable, got their types in each typed rule
+    #[clap(long)]
+    explain: Option<String>,
 
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
+    /// Exit with an error if any selected rule couldn't be typed
+    #[clap(long, action=ArgAction::SetTrue)]
+    fail_on_skip: bool,
 
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(tx, annotation_ir::Type::BitVector));
+    /// Check the inferred types against the concrete test in this file
+    #[clap(long)]
+    concrete: Option<String>,
 
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::Int));
+    /// Write the inferred types as JSON to this path
+    #[clap(long)]
+    json: Option<String>,
 
-            (veri_ir::Expr::BVToInt(Box::new(ex)), t)
-        }
-        annotation_ir::Expr::Conditional(c, t, e) => {
-            let (e1, t1) = add_annotation_constraints(*c, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*t, tree, annotation_info);
-            let (e3, t3) = add_annotation_constraints(*e, tree, annotation_info);
-            let t = tree.next_type_var;
-
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::Bool));
-            tree.var_constraints.insert(TypeExpr::Variable(t2, t3));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::Conditional(Box::new(e1), Box::new(e2), Box::new(e3)),
-                t,
-            )
-        }
-        annotation_ir::Expr::Switch(c, cases) => {
-            let (c_expr, c_t) = add_annotation_constraints(*c, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            let mut case_exprs = vec![];
-            for (m, b) in cases {
-                let (case_expr, case_t) =
-                    add_annotation_constraints(m.clone(), tree, annotation_info);
-                let (body_expr, body_t) =
-                    add_annotation_constraints(b.clone(), tree, annotation_info);
-
-                tree.var_constraints.insert(TypeExpr::Variable(c_t, case_t));
-                tree.var_constraints.insert(TypeExpr::Variable(t, body_t));
-                case_exprs.push((case_expr, body_expr));
-            }
-            (veri_ir::Expr::Switch(Box::new(c_expr), case_exprs), t)
-        }
-        annotation_ir::Expr::CLZ(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::CLZ(Box::new(e1)), t)
-        }
-        annotation_ir::Expr::A64CLZ(ty, x) => {
-            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(REG_WIDTH),
-            ));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    /// Write a CSV table of each typed rule's annotation variables and their
+    /// types to this path
+    #[clap(long)]
+    csv: Option<String>,
 
-            tree.next_type_var += 1;
-            (veri_ir::Expr::A64CLZ(Box::new(e0), Box::new(e1)), t)
-        }
-        annotation_ir::Expr::CLS(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::CLS(Box::new(e1)), t)
-        }
-        annotation_ir::Expr::A64CLS(ty, x) => {
-            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(REG_WIDTH),
-            ));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    /// Write the typed rules as parseable ISLE, with types in comments, to this path
+    #[clap(long)]
+    emit_isle: Option<String>,
 
-            tree.next_type_var += 1;
-            (veri_ir::Expr::A64CLS(Box::new(e0), Box::new(e1)), t)
-        }
-        annotation_ir::Expr::Rev(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
-
-            tree.next_type_var += 1;
-            (veri_ir::Expr::Rev(Box::new(e1)), t)
-        }
-        annotation_ir::Expr::A64Rev(ty, x) => {
-            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-
-            let t = tree.next_type_var;
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(REG_WIDTH),
-            ));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
+    /// Write each typed rule's type constraints as a Graphviz DOT graph to this
+    /// path. With `--output-dir`, each rule's graph gets its own file named
+    /// after this path, the term, the instantiation, and the rule.
+    #[clap(long)]
+    dot: Option<String>,
 
-            tree.next_type_var += 1;
-            (veri_ir::Expr::A64Rev(Box::new(e0), Box::new(e1)), t)
-        }
-        annotation_ir::Expr::BVSubs(ty, x, y) => {
-            let (e0, t0) = add_annotation_constraints(*ty, tree, annotation_info);
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
-            let (e2, t2) = add_annotation_constraints(*y, tree, annotation_info);
-
-            let t = tree.next_type_var;
-
-            // For aarch64, subs sets 4 flags. Model these as 4 bit appended to the left of the
-            // register.
-            tree.concrete_constraints.insert(TypeExpr::Concrete(
-                t,
-                annotation_ir::Type::BitVectorWithWidth(REG_WIDTH + FLAGS_WIDTH),
-            ));
-            tree.concrete_constraints
-                .insert(TypeExpr::Concrete(t0, annotation_ir::Type::Int));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t2, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t1, t2));
-
-            tree.next_type_var += 1;
-            (
-                veri_ir::Expr::BVSubs(Box::new(e0), Box::new(e1), Box::new(e2)),
-                t,
-            )
-        }
-        annotation_ir::Expr::BVPopcnt(x) => {
-            let (e1, t1) = add_annotation_constraints(*x, tree, annotation_info);
+    /// Write the SMT log, JSON, ISLE, and DOT outputs under this directory,
+    /// creating it if needed
+    #[clap(long)]
+    output_dir: Option<String>,
 
-            let t = tree.next_type_var;
+    /// Parse the inputs and resolve their terms and annotations, then exit
+    /// without solving
+    #[clap(long, action=ArgAction::SetTrue)]
+    no_solve: bool,
 
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t1, annotation_ir::Type::BitVector));
-            tree.bv_constraints
-                .insert(TypeExpr::Concrete(t, annotation_ir::Type::BitVector));
-            tree.var_constraints.insert(TypeExpr::Variable(t, t1));
+    /// List every term and whether it is annotated, then exit
+    #[clap(long, action=ArgAction::SetTrue)]
+    list_terms: bool,
 
-            tree.next_type_var += 1;
-            (veri_ir::Expr::BVPopcnt(Box::new(e1)), t)
-        }
-    };
-    tree.ty_vars.insert(e.clone(), t);
-    (e, t)
+    /// Report which terms used by the selected rules lack annotations, then exit
+    #[clap(long, action=ArgAction::SetTrue)]
+    coverage: bool,
 }
 
-fn add_isle_constraints(
-    term: &sema::Term,
-    tree: &mut RuleParseTree,
-    annotation_env: &AnnotationEnv,
-    annotation_info: &mut AnnotationTypeInfo,
-    annotation: annotation_ir::TermSignature,
-) {
-    let mut annotation_vars = vec![];
-    for a in annotation.args {
-        annotation_vars.push(a.name);
-    }
-    annotation_vars.push(annotation.ret.name);
-
-    let mut isle_types = vec![];
-    for arg_ty in term.arg_tys.iter() {
-        isle_types.push(arg_ty.clone());
-    }
-    isle_types.push(term.ret_ty.clone());
-    assert_eq!(annotation_vars.len(), isle_types.len());
-
-    for (isle_type_id, annotation_var) in isle_types.iter().zip(annotation_vars) {
-        // in case the var was not in the annotation
-        if !annotation_info
-            .var_to_type_var
-            .contains_key(&annotation_var)
-        {
-            let type_var = tree.next_type_var;
-            tree.next_type_var += 1;
-
-            annotation_info
-                .var_to_type_var
-                .insert(annotation_var.clone(), type_var);
-        }
-
-        if let Some(ir_type) = annotation_env.model_map.get(isle_type_id) {
-            let type_var = annotation_info.var_to_type_var[&annotation_var];
-            match ir_type {
-                annotation_ir::Type::BitVector => tree
-                    .bv_constraints
-                    .insert(TypeExpr::Concrete(type_var, ir_type.clone())),
-                _ => tree
-                    .concrete_constraints
-                    .insert(TypeExpr::Concrete(type_var, ir_type.clone())),
-            };
+// Check that a root term has signatures to type its rules with. Otherwise
+// list the terms that do, suggesting the closest name.
+fn check_root_term(
+    term: &str,
+    term_signatures: &HashMap<String, Vec<TermSignature>>,
+) -> Result<(), String> {
+    if term_signatures.contains_key(term) {
+        return Ok(());
+    }
+    let mut msg = format!("No instantiations for root term `{}`", term);
+    let closest = term_signatures
+        .keys()
+        .map(|name| (edit_distance(term, name), name))
+        .min();
+    if let Some((distance, name)) = closest {
+        if distance <= (term.len() / 3).max(2) {
+            msg.push_str(&format!("; did you mean `{}`?", name));
+        }
+    }
+    if term_signatures.is_empty() {
+        msg.push_str("\nNo terms have instantiations");
+    } else {
+        msg.push_str("\nTerms with instantiations:");
+        for name in term_signatures.keys().sorted() {
+            msg.push_str(&format!("\n\t{}", name));
         }
     }
+    Err(msg)
 }
 
-fn add_rule_constraints(
-    tree: &mut RuleParseTree,
-    curr: &mut TypeVarNode,
-    termenv: &TermEnv,
-    typeenv: &TypeEnv,
-    annotation_env: &AnnotationEnv,
-    annotation_infos: &mut Vec<AnnotationTypeInfo>,
-    rhs: bool,
-) -> Option<veri_ir::Expr> {
-    // Only relate args to annotations for terms. For leaves, return immediately.
-    // For recursive definitions without annotations (like And and Let), recur.
-    let mut children = vec![];
-    for child in &mut curr.children {
-        if let Some(e) = add_rule_constraints(
-            tree,
-            child,
-            termenv,
-            typeenv,
-            annotation_env,
-            annotation_infos,
-            rhs,
-        ) {
-            children.push(e);
-        } else {
-            return None;
+// Levenshtein distance between two names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            curr.push(substitute.min(prev[j + 1] + 1).min(curr[j] + 1));
         }
+        prev = curr;
     }
-    let e = match &curr.construct {
-        TypeVarConstruct::Var => {
-            tree.quantified_vars
-                .insert(curr.ident.clone(), curr.type_var);
-            tree.free_vars.insert(curr.ident.clone(), curr.type_var);
-            Some(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
-                curr.ident.clone(),
-            )))
-        }
-        TypeVarConstruct::BindPattern => {
-            assert_eq!(children.len(), 2);
-            tree.assumptions.push(veri_ir::Expr::Binary(
-                veri_ir::BinaryOp::Eq,
-                Box::new(children[0].clone()),
-                Box::new(children[1].clone()),
-            ));
-            Some(children[0].clone())
-        }
-        TypeVarConstruct::Wildcard(i) => {
-            Some(veri_ir::Expr::Terminal(veri_ir::Terminal::Wildcard(*i)))
-        }
-        TypeVarConstruct::Const(i) => {
-            // If constant is known, add the value to the tree. Useful for
-            // capturing isleTypes
-            tree.type_var_to_val_map.insert(curr.type_var, *i);
-
-            Some(veri_ir::Expr::Terminal(veri_ir::Terminal::Const(
-                *i,
-                curr.type_var,
-            )))
-        }
-        TypeVarConstruct::And => {
-            tree.quantified_vars
-                .insert(curr.ident.clone(), curr.type_var);
-            let first = &children[0];
-            for (i, e) in children.iter().enumerate() {
-                if i != 0 {
-                    tree.assumptions.push(veri_ir::Expr::Binary(
-                        veri_ir::BinaryOp::Eq,
-                        Box::new(first.clone()),
-                        Box::new(e.clone()),
-                    ))
-                }
-            }
-            Some(first.to_owned())
-        }
-        TypeVarConstruct::Let(bound) => {
-            tree.quantified_vars
-                .insert(curr.ident.clone(), curr.type_var);
-            for (e, s) in children.iter().zip(bound) {
-                tree.assumptions.push(veri_ir::Expr::Binary(
-                    veri_ir::BinaryOp::Eq,
-                    Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
-                        s.to_owned(),
-                    ))),
-                    Box::new(e.to_owned()),
-                ))
-            }
-            children.last().cloned()
-        }
-        TypeVarConstruct::Term(term_id) => {
-            let term = &termenv.terms[term_id.index()];
-            let term_name = typeenv.syms[term.name.index()].clone();
-
-            // Print term for debugging
-            print!(" {}", term_name);
-
-            tree.quantified_vars
-                .insert(curr.ident.clone(), curr.type_var);
-            let a = annotation_env.get_annotation_for_term(term_id);
-            if a.is_none() {
-                println!("\nSkipping rule with unannotated term: {}", term_name);
-                return None;
-            }
-            let annotation = a.unwrap();
-
-            // use a fresh mapping for each term
-            // keep the same mapping between assertions in the same annotation
-            let mut annotation_info = AnnotationTypeInfo {
-                term: curr.ident.clone(),
-                var_to_type_var: HashMap::new(),
-            };
-            for expr in annotation.assumptions {
-                let (typed_expr, _) = add_annotation_constraints(*expr, tree, &mut annotation_info);
-                curr.assertions.push(typed_expr.clone());
-                tree.assumptions.push(typed_expr);
-                add_isle_constraints(
-                    term,
-                    tree,
-                    annotation_env,
-                    &mut annotation_info,
-                    annotation.sig.clone(),
-                );
-            }
-            // For assertions, global assume if not RHS, otherwise assert
-            for expr in annotation.assertions {
-                let (typed_expr, _) = add_annotation_constraints(*expr, tree, &mut annotation_info);
-                curr.assertions.push(typed_expr.clone());
-                add_isle_constraints(
-                    term,
-                    tree,
-                    annotation_env,
-                    &mut annotation_info,
-                    annotation.sig.clone(),
-                );
-                if rhs {
-                    tree.rhs_assertions.push(typed_expr);
-                } else {
-                    tree.assumptions.push(typed_expr);
-                }
-            }
-
-            // set args in rule equal to args in annotation
-            for (child, arg) in curr.children.iter().zip(&annotation.sig.args) {
-                let rule_type_var = child.type_var;
-                let annotation_type_var = annotation_info.var_to_type_var[&arg.name];
-
-                // essentially constant propagate: if we know the value from the rule arg being
-                // provided as a literal, propagate this to the annotation.
-                if let Some(c) = tree.type_var_to_val_map.get(&rule_type_var) {
-                    tree.type_var_to_val_map.insert(annotation_type_var, *c);
-                }
-                tree.var_constraints
-                    .insert(TypeExpr::Variable(rule_type_var, annotation_type_var));
-            }
-
-            for (child, arg) in children.iter().zip(&annotation.sig.args) {
-                let annotation_type_var = annotation_info.var_to_type_var[&arg.name];
-                let arg_name = format!(
-                    "{}__{}__{}",
-                    annotation_info.term, arg.name, annotation_type_var
-                );
-                tree.quantified_vars
-                    .insert(arg_name.clone(), annotation_type_var);
-                tree.assumptions.push(veri_ir::Expr::Binary(
-                    veri_ir::BinaryOp::Eq,
-                    Box::new(child.clone()),
-                    Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(arg_name))),
-                ))
-            }
-            // set term ret var equal to annotation ret var
-            let ret_var = annotation_info.var_to_type_var[&annotation.sig.ret.name];
-            tree.var_constraints
-                .insert(TypeExpr::Variable(curr.type_var, ret_var));
-            let ret_name = format!(
-                "{}__{}__{}",
-                annotation_info.term, annotation.sig.ret.name, ret_var
-            );
-            tree.quantified_vars.insert(ret_name.clone(), ret_var);
-            tree.assumptions.push(veri_ir::Expr::Binary(
-                veri_ir::BinaryOp::Eq,
-                Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
-                    curr.ident.clone(),
-                ))),
-                Box::new(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(ret_name))),
-            ));
-
-            annotation_infos.push(annotation_info);
-
-            Some(veri_ir::Expr::Terminal(veri_ir::Terminal::Var(
-                curr.ident.clone(),
-            )))
-        }
-    };
-    if let Some(e) = e {
-        tree.ty_vars.insert(e.clone(), curr.type_var);
-        Some(e)
-    } else {
-        None
-    }
-}
-
-fn solve_constraints(
-    concrete: &HashSet<TypeExpr>,
-    var: &HashSet<TypeExpr>,
-    bv: &HashSet<TypeExpr>,
-    vals: &mut HashMap<u32, i128>,
-    _lhs_expr: &Expr,
-    _rhs_expr: &Expr,
-    //ty_vars: Option<&HashMap<veri_ir::Expr, u32>>,
-) -> (HashMap<u32, annotation_ir::Type>, HashMap<u32, u32>) {
-    // Setup
-    let smt = easy_smt::ContextBuilder::new()
-        .replay_file(Some(std::fs::File::create("type_solver.smt2").unwrap()))
-        .solver("z3", ["-smt2", "-in"])
-        .build()
-        .unwrap();
-
-    let mut solver = TypeSolver::new(smt);
-    solver.add_constraints(concrete);
-    solver.add_constraints(var);
-    solver.add_constraints(bv);
-    solver.set_values(vals);
-
-    let result = solver.solve();
-
-    let bv_unknown_width_sets = HashMap::new();
-    (result, bv_unknown_width_sets)
+    prev[b.len()]
 }
 
-struct TypeSolver {
-    smt: easy_smt::Context,
-
-    // Symbolic type for each type variable.
-    symbolic_types: HashMap<u32, SymbolicType>,
-}
-
-impl TypeSolver {
-    fn new(smt: easy_smt::Context) -> Self {
-        Self {
-            smt,
-            symbolic_types: HashMap::new(),
-        }
+fn list_terms(termenv: &TermEnv, typeenv: &TypeEnv, annotation_env: &AnnotationEnv) {
+    let ty_name = |ty: &sema::TypeId| typeenv.types[ty.index()].name(typeenv).to_string();
+    let annotated_terms: HashSet<sema::TermId> = annotation_env.annotated_terms().collect();
+    for (i, term) in termenv.terms.iter().enumerate() {
+        let name = &typeenv.syms[term.name.index()];
+        let annotated = annotated_terms.contains(&sema::TermId(i));
+        println!(
+            "{}\t({}) {}\tannotated: {}",
+            name,
+            term.arg_tys.iter().map(ty_name).join(" "),
+            ty_name(&term.ret_ty),
+            annotated
+        );
     }
+}
 
-    fn solve(&mut self) -> HashMap<u32, annotation_ir::Type> {
-        let response = self.smt.check().unwrap();
-        assert_eq!(response, Response::Sat);
+fn main() {
+    let args = Args::parse();
+    let mut inputs = vec![];
 
-        let vs: Vec<_> = self.symbolic_types.keys().copied().collect();
-        let mut tys = HashMap::new();
-        for v in vs {
-            tys.insert(v, self.get_type(v));
-        }
-        tys
+    let mut arches = args.arch.clone();
+    if args.aarch64 {
+        arches.push("aarch64".to_string());
     }
+    arches.sort();
+    arches.dedup();
 
-    fn get_type(&mut self, v: u32) -> annotation_ir::Type {
-        let symbolic_type = self.get_symbolic_type(v);
-
-        // Lookup discriminant from the model.
-        let discriminant_value =
-            usize::try_from(self.get_value_data(symbolic_type.discriminant.expr))
-                .expect("discriminant value should be integer");
-        let discriminant =
-            TypeDiscriminant::from_repr(discriminant_value).expect("unknown discriminant value");
-
-        match discriminant {
-            TypeDiscriminant::BitVector => {
-                // Is the bitvector width known?
-                let has_width = self.get_bool_value(symbolic_type.bitvector_width.some.expr);
-                if !has_width {
-                    return annotation_ir::Type::BitVector;
-                }
-
-                // Lookup width.
-                let width =
-                    usize::try_from(self.get_value_data(symbolic_type.bitvector_width.value.expr))
-                        .expect("bitvector width should be integer");
+    let cur_dir = env::current_dir().expect("Can't access current working directory");
+    let ref_dir = cur_dir.join("ref");
+    if !args.noprelude {
+        // Build the relevant ISLE prelude using the meta crate. An ISA named
+        // by both --isa and --arch, or by two of its aliases, is built once.
+        let isas: Vec<Isa> = args
+            .isa
+            .iter()
+            .chain(&arches)
+            .map(|arch| Isa::from_arch(arch).unwrap_or_else(|| panic!("Unknown ISA: {}", arch)))
+            .unique_by(|isa| isa.to_string())
+            .collect();
+        let clif_lower_isle = build_clif_lower_isle(&isas)
+            .unwrap_or_else(|err| panic!("Could not build the CLIF prelude: {}", err));
+        inputs.push(clif_lower_isle);
 
-                annotation_ir::Type::BitVectorWithWidth(width)
-            }
-            TypeDiscriminant::Bool => annotation_ir::Type::Bool,
-            TypeDiscriminant::Int => annotation_ir::Type::Int,
-        }
+        inputs.push(ref_dir.join("inst_specs.isle"));
+        inputs.push(ref_dir.join("prelude.isle"));
+        inputs.push(ref_dir.join("prelude_lower.isle"));
     }
 
-    fn get_bool_value(&mut self, expr: SExpr) -> bool {
-        let value = self.get_value(expr);
-        if value == self.smt.true_() {
-            true
-        } else if value == self.smt.false_() {
-            false
-        } else {
-            unreachable!("value is not boolean");
+    // DO NOT include these for broken tests
+    for arch in &arches {
+        let arch_dir = ref_dir.join(arch);
+        if !arch_dir.is_dir() {
+            panic!("Missing ISLE files for {}: {}", arch, arch_dir.display());
         }
-    }
-
-    fn get_value_data(&mut self, expr: SExpr) -> SExprData {
-        let value = self.get_value(expr);
-        self.smt.get(value)
-    }
-
-    fn get_value(&mut self, expr: SExpr) -> SExpr {
-        let values = self.smt.get_value(vec![expr]).unwrap();
-        assert_eq!(values.len(), 1);
-        values[0].1
-    }
-
-    fn add_constraints(&mut self, type_exprs: &HashSet<TypeExpr>) {
-        for type_expr in type_exprs {
-            self.add_constraint(type_expr);
+        inputs.push(arch_dir.join("inst.isle"));
+        // Not every architecture has specs yet
+        let specs = arch_dir.join("inst_specs.isle");
+        if specs.is_file() {
+            inputs.push(specs);
         }
+        inputs.push(arch_dir.join("lower.isle"));
     }
 
-    fn add_constraint(&mut self, type_expr: &TypeExpr) {
-        match type_expr {
-            TypeExpr::Concrete(v, ty) => self.concrete(*v, ty),
-            TypeExpr::Variable(u, v) => self.variable(*u, *v),
-            TypeExpr::WidthInt(v, w) => self.width_int(*v, *w),
-            TypeExpr::Symbolic(l, r) => self.symbolic_sum(l.clone(), r.clone()),
-        }
+    if args.input.is_empty() && arches.is_empty() {
+        panic!("Missing input file; pass --input or --arch");
     }
+    inputs.extend(args.input.iter().map(PathBuf::from));
 
-    fn set_values(&mut self, vals: &HashMap<u32, i128>) {
-        for (v, n) in vals {
-            self.set_value(*v, *n);
+    let mut program = parse_program(&inputs).unwrap_or_else(|err| panic!("{}", err));
+    // Annotations kept apart from the rules, e.g. for files that can't be edited
+    for path in &args.annotations {
+        if let Err(err) = program.merge_annotation_file(Path::new(path)) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
     }
+    let Program {
+        typeenv: tyenv,
+        termenv,
+        annotation_env,
+        ..
+    } = &program;
 
-    fn set_value(&mut self, v: u32, n: i128) {
-        // If it's an integer, it should have this value.
-        let symbolic_type = self.get_symbolic_type(v);
-        self.smt
-            .assert(
-                self.smt.imp(
-                    self.smt.eq(
-                        symbolic_type.discriminant.expr,
-                        self.smt.numeral(TypeDiscriminant::Int as u8),
-                    ),
-                    self.smt.and(
-                        symbolic_type.integer_value.some.expr,
-                        self.smt
-                            .eq(symbolic_type.integer_value.value.expr, self.smt.numeral(n)),
-                    ),
-                ),
-            )
-            .unwrap();
+    if args.list_terms {
+        list_terms(termenv, tyenv, annotation_env);
+        return;
     }
 
-    fn concrete(&mut self, v: u32, ty: &annotation_ir::Type) {
-        let symbolic_type = self.get_symbolic_type(v);
-        match ty {
-            annotation_ir::Type::BitVector => {
-                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::BitVector);
-            }
-            annotation_ir::Type::BitVectorWithWidth(w) => {
-                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::BitVector);
-                self.assert_option_value(&symbolic_type.bitvector_width, self.smt.numeral(*w));
-            }
-            annotation_ir::Type::Int => {
-                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::Int)
-            }
-            annotation_ir::Type::Bool => {
-                self.assert_type_discriminant(&symbolic_type, TypeDiscriminant::Bool)
-            }
-            _ => todo!("concrete: {ty:?}"),
+    let term_signatures = program.term_signatures();
+    let terms: Vec<String> = if args.all_terms {
+        term_signatures.keys().sorted().cloned().collect()
+    } else {
+        args.term.clone()
+    };
+    let mut ok = true;
+    for term in &terms {
+        if let Err(msg) = check_root_term(term, term_signatures) {
+            eprintln!("{}", msg);
+            ok = false;
         }
     }
-
-    fn variable(&mut self, u: u32, v: u32) {
-        let a = self.get_symbolic_type(u);
-        let b = self.get_symbolic_type(v);
-        self.assert_types_equal(&a, &b);
-    }
-
-    fn width_int(&mut self, v: u32, w: u32) {
-        // Type v is a bitvector, type w is an integer, and the bitwidth of v is
-        // equal to the value of w.
-        let bitvector_type = self.get_symbolic_type(v);
-        let width_type = self.get_symbolic_type(w);
-
-        self.assert_type_discriminant(&bitvector_type, TypeDiscriminant::BitVector);
-        self.assert_type_discriminant(&width_type, TypeDiscriminant::Int);
-        self.assert_options_equal(&bitvector_type.bitvector_width, &width_type.integer_value)
-    }
-
-    fn symbolic_sum(&mut self, l: Vec<u32>, r: Vec<u32>) {
-        // get the expressions of each bv we want to add
-        let l_widths: Vec<SExpr> = l
-            .iter()
-            .map(|s| self.get_symbolic_type(*s).bitvector_width.value.expr)
-            .collect();
-        // sum them together
-        let l_sum = self.smt.plus_many(l_widths);
-
-        // same for rhs
-        let r_widths: Vec<SExpr> = r
-            .iter()
-            .map(|s| self.get_symbolic_type(*s).bitvector_width.value.expr)
-            .collect();
-        let r_sum = self.smt.plus_many(r_widths);
-        self.smt.assert(self.smt.eq(l_sum, r_sum)).unwrap();
+    if !ok {
+        std::process::exit(1);
     }
 
-    fn assert_type_discriminant(&mut self, symbolic_type: &SymbolicType, disc: TypeDiscriminant) {
-        let disc = self.smt.numeral(disc as u8);
-        let eq = self.smt.eq(symbolic_type.discriminant.expr, disc);
-        self.smt.assert(eq).unwrap();
-    }
-
-    fn assert_option_value(&mut self, symbolic_option: &SymbolicOption, value: SExpr) {
-        self.smt.assert(symbolic_option.some.expr).unwrap();
-        self.smt
-            .assert(self.smt.eq(symbolic_option.value.expr, value))
-            .unwrap();
-    }
+    let names = if let Some(names) = args.names {
+        let mut names = names;
+        names.sort();
+        names.dedup();
+        Some(names)
+    } else {
+        None
+    };
 
-    fn assert_types_equal(&mut self, a: &SymbolicType, b: &SymbolicType) {
-        // Note that we assert nothing about the integer value, only that the
-        // types are the same.
-        self.assert_variables_equal(&a.discriminant, &b.discriminant);
-        self.assert_options_equal(&a.bitvector_width, &b.bitvector_width);
-    }
+    let verbosity = if args.quiet {
+        Verbosity::Quiet
+    } else if args.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    };
 
-    fn assert_options_equal(&mut self, a: &SymbolicOption, b: &SymbolicOption) {
-        self.assert_variables_equal(&a.some, &b.some);
-        self.assert_variables_equal(&a.value, &b.value);
+    let output_dir = args.output_dir.as_ref().map(PathBuf::from);
+    if let Some(dir) = &output_dir {
+        std::fs::create_dir_all(dir).expect("Could not create output directory");
     }
+    let output_path = |path: &str| match &output_dir {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    };
 
-    fn assert_variables_equal(&mut self, a: &SymbolicVariable, b: &SymbolicVariable) {
-        self.smt.assert(self.smt.eq(a.expr, b.expr)).unwrap();
-    }
+    let mut config = Config {
+        // Set to each root term in turn below
+        term: String::new(),
+        names: names,
+        names_regex: args.names_regex,
+        exclude_names: args.exclude_names,
+        smt_log: if args.no_smt_log {
+            None
+        } else {
+            Some(output_path(&args.smt_log).to_string_lossy().into_owned())
+        },
+        solver: args.solver,
+        parallel: args.parallel,
+        reporter: if args.only_failing {
+            Reporter::new(verbosity).only_failing()
+        } else {
+            Reporter::new(verbosity)
+        },
+        timeout: args.timeout,
+        check_unique: args.check_unique,
+        dump_constraints: args.dump_constraints,
+        emit_isle: args.emit_isle.is_some(),
+        emit_dot: args.dot.is_some(),
+        assume_identity: args.assume_identity,
+        stats: args.stats,
+        target: TargetConfig {
+            reg_width: args.reg_width,
+            flags_width: args.flags_width,
+        },
+        strict_conv_to: args.strict_conv_to,
+        switch_coverage: args.switch_coverage,
+        narrow_shift_amounts: args.narrow_shift_amounts,
+        max_width: args.max_width,
+        poly_nullary: args.poly_nullary,
+        explain: args.explain,
+    };
 
-    fn get_symbolic_type(&mut self, v: u32) -> SymbolicType {
-        self.symbolic_types
-            .entry(v)
-            .or_insert_with(|| SymbolicType::decl(&mut self.smt, v))
-            .clone()
-    }
-    fn display_isle_pattern(
-        &mut self,
-        termenv: &TermEnv,
-        typeenv: &TypeEnv,
-        rule: &sema::Rule,
-        annotation_infos: &Vec<AnnotationTypeInfo>,
-        type_sols: &HashMap<u32, veri_ir::annotation_ir::Type>,
-        pat: &Pattern,
-        parent_term: Option<&AnnotationTypeInfo>,
-    ) -> SExpr {
-        let mut to_sexpr =
-            |ai, p, pt| self.display_isle_pattern(termenv, typeenv, rule, ai, type_sols, p, pt);
-
-        let mut anno = annotation_infos.clone();
-        match pat {
-            sema::Pattern::Term(_, term_id, args) => {
-                let sym = termenv.terms[term_id.index()].name;
-                let name = typeenv.syms[sym.index()].clone();
-
-                let matches: Vec<&AnnotationTypeInfo> = annotation_infos
-                    .iter()
-                    .filter(|t| t.term.starts_with(&name))
-                    .collect();
-
-                let mut var = " ".to_string();
-                if matches.len() == 0 {
-                    println!("Can't find match for: {}", name);
-                    panic!();
-                } else if matches.len() >= 1 {
-                    var = format!(
-                        "[{}|{}]",
-                        type_to_num(
-                            type_sols
-                                .get(
-                                    &matches
-                                        .first()
-                                        .unwrap()
-                                        .var_to_type_var
-                                        .get("result")
-                                        .unwrap()
-                                )
-                                .unwrap()
-                        ),
-                        name
-                    );
-                    if matches.len() > 1 {
-                        let index = annotation_infos
-                            .iter()
-                            .position(|t| t.term == matches.first().unwrap().term)
-                            .unwrap();
-                        anno.remove(index);
-                    }
+    if args.coverage {
+        for term in &terms {
+            config.term = term.clone();
+            let coverage = annotation_coverage(termenv, tyenv, annotation_env, &config);
+            let total = coverage.annotated.len() + coverage.unannotated.len();
+            println!(
+                "{}/{} terms reachable from {} are annotated",
+                coverage.annotated.len(),
+                total,
+                config.term
+            );
+            if !coverage.unannotated.is_empty() {
+                println!("Unannotated:");
+                for name in &coverage.unannotated {
+                    println!("\t{}", name);
                 }
-
-                let mut sexprs: Vec<SExpr> = args
-                    .iter()
-                    .map(|a| to_sexpr(&anno, a, matches.first().copied()))
-                    .collect::<Vec<SExpr>>();
-
-                sexprs.insert(0, self.smt.atom(var));
-                self.smt.list(sexprs)
             }
-
-            sema::Pattern::Var(_, var_id) => {
-                let sym = rule.vars[var_id.index()].name;
-                let ident = typeenv.syms[sym.index()].clone();
-
-                let mut var = " ".to_string();
-                match parent_term {
-                    Some(value) => {
-                        var = format!(
-                            "[{}|{}]",
-                            type_to_num(
-                                type_sols
-                                    .get(&value.var_to_type_var.get(&ident).unwrap())
-                                    .unwrap()
-                            ),
-                            ident
-                        );
-                    }
-                    None => print!("Not found!"),
-                }
-
-                self.smt.atom(var)
+        }
+        return;
+    }
+
+    if args.no_solve {
+        // The inputs parsed and their root terms have signatures; report what
+        // would keep rules from being typed, without launching the solver
+        for term in &terms {
+            config.term = term.clone();
+            let coverage = annotation_coverage(termenv, tyenv, annotation_env, &config);
+            for name in &coverage.unannotated {
+                println!("Unannotated term reachable from {}: {}", term, name);
+            }
+        }
+        return;
+    }
+
+    let concrete = args.concrete.as_ref().map(|path| {
+        let src = std::fs::read_to_string(path).expect("Could not read concrete test file");
+        parse_concrete_test(&src).unwrap_or_else(|err| panic!("Bad concrete test: {}", err))
+    });
+
+    // A single solver process is shared by every rule; each rule's
+    // constraints are scoped with push/pop.
+    let mut solver = TypeSolver::new(&config);
+
+    let mut json = vec![];
+    let mut csv = String::from("rule,term,variable,type\n");
+    let mut isle = String::new();
+    let mut dot = String::new();
+    let mut stats = vec![];
+    // Rules selected and typed across every term and instantiation
+    let mut selected = 0;
+    let mut typed = 0;
+    // Rules typed and selected for each term, in the order they were typed
+    let mut term_counts = vec![];
+    for term in &terms {
+        config.term = term.clone();
+        let (term_selected, term_typed) = (selected, typed);
+        if terms.len() > 1 {
+            config
+                .reporter
+                .info(format_args!("Typing rules rooted at {}", term));
+        }
+
+        // One map of results per instantiation of the term, in the same order
+        let results = program
+            .try_infer_with(&mut solver, &config, &concrete)
+            .unwrap_or_else(|err| panic!("{}", err));
+        for (instantiation, results) in results.into_iter().enumerate() {
+            let type_instantiation = &term_signatures[term][instantiation];
+            selected += results.len();
+            let type_sols: HashMap<_, _> = results
+                .into_iter()
+                .filter_map(|(id, result)| Some((id, result.ok()?)))
+                .collect();
+            typed += type_sols.len();
+            if args.csv.is_some() {
+                csv.push_str(&csv_rows(termenv, tyenv, &type_sols));
+            }
+            if args.json.is_some() {
+                json.push(InstantiationJson::new(
+                    termenv,
+                    tyenv,
+                    &config,
+                    type_instantiation,
+                    &type_sols,
+                ));
             }
-            sema::Pattern::BindPattern(_, var_id, subpat) => {
-                let sym = rule.vars[var_id.index()].name;
-                let ident = &typeenv.syms[sym.index()];
-                let subpat_node = to_sexpr(annotation_infos, subpat, parent_term);
-
-                let mut var = " ".to_string();
-                match parent_term {
-                    Some(value) => match value.var_to_type_var.get(ident) {
-                        Some(ty) => {
-                            var =
-                                format!("[{}|{}]", type_to_num(type_sols.get(ty).unwrap()), ident);
-                        }
-                        None => {
-                            var = format!(
-                                "[{}|{}]",
-                                type_to_num(
-                                    type_sols
-                                        .get(&value.var_to_type_var.get("arg").unwrap())
-                                        .unwrap()
-                                ),
-                                ident
-                            );
-                        }
-                    },
-                    None => print!("Not found!"),
-                }
-                // Special case: elide bind patterns to wildcars
-                if matches!(**subpat, sema::Pattern::Wildcard(_)) {
-                    self.smt.atom(&var)
-                } else {
-                    self.smt
-                        .list(vec![self.smt.atom(&var), self.smt.atom("@"), subpat_node])
+            if args.emit_isle.is_some() {
+                isle.push_str(&format!(
+                    ";; {} instantiation: {:?}\n",
+                    term, type_instantiation
+                ));
+                for (_, semantics) in type_sols.iter().sorted_by_key(|(id, _)| id.index()) {
+                    isle.push_str(semantics.isle.as_deref().unwrap_or_default());
                 }
-            }
-            sema::Pattern::Wildcard(_) => self.smt.list(vec![self.smt.atom("_")]),
-            sema::Pattern::ConstPrim(_, sym) => {
-                let name = typeenv.syms[sym.index()].clone();
-                self.smt.list(vec![self.smt.atom(name)])
-            }
-            sema::Pattern::ConstInt(_, num) => {
-                let _smt_name_prefix = format!("{}__", num);
-                // TODO: look up BV vs int
-                self.smt.list(vec![self.smt.atom(num.to_string())])
-            }
-            sema::Pattern::And(_, subpats) => {
-                let mut sexprs = subpats
-                    .iter()
-                    .map(|a| to_sexpr(annotation_infos, a, parent_term))
-                    .collect::<Vec<SExpr>>();
-
-                sexprs.insert(0, self.smt.atom("and"));
-                self.smt.list(sexprs)
-            }
-        }
-    }
-    fn display_isle_expr(
-        &self,
-        termenv: &TermEnv,
-        typeenv: &TypeEnv,
-        rule: &sema::Rule,
-        annotation_infos: &Vec<AnnotationTypeInfo>,
-        type_sols: &HashMap<u32, veri_ir::annotation_ir::Type>,
-        expr: &sema::Expr,
-        parent_term: Option<&AnnotationTypeInfo>,
-    ) -> SExpr {
-        let to_sexpr =
-            |ai, e, pt| self.display_isle_expr(termenv, typeenv, rule, ai, type_sols, e, pt);
-
-        let mut anno = annotation_infos.clone();
-
-        match expr {
-            sema::Expr::Term(_, term_id, args) => {
-                let sym = termenv.terms[term_id.index()].name;
-                let name = typeenv.syms[sym.index()].clone();
-
-                let matches: Vec<&AnnotationTypeInfo> = annotation_infos
-                    .iter()
-                    .filter(|t| t.term.starts_with(&name))
-                    .collect();
-
-                let mut var = " ".to_string();
-                if matches.len() == 0 {
-                    println!("Can't find match for: {}", name);
-                    panic!();
-                } else if matches.len() >= 1 {
-                    var = format!(
-                        "[{}|{}]",
-                        type_to_num(
-                            type_sols
-                                .get(
-                                    &matches
-                                        .first()
-                                        .unwrap()
-                                        .var_to_type_var
-                                        .get("result")
-                                        .unwrap()
-                                )
-                                .unwrap()
-                        ),
-                        name
-                    );
-                    if matches.len() > 1 {
-                        let index = annotation_infos
-                            .iter()
-                            .position(|t| t.term == matches.first().unwrap().term)
-                            .unwrap();
-                        anno.remove(index);
+                isle.push('\n');
+            }
+            if let Some(path) = &args.dot {
+                for (id, semantics) in type_sols.iter().sorted_by_key(|(id, _)| id.index()) {
+                    let graph = semantics.dot.as_deref().unwrap_or_default();
+                    if output_dir.is_some() {
+                        let rule = rule_name(&termenv.rules[id.index()], tyenv);
+                        let file = per_rule_file_name(path, term, instantiation, &rule);
+                        std::fs::write(output_path(&file), graph)
+                            .expect("Could not write DOT output");
+                    } else {
+                        dot.push_str(graph);
                     }
                 }
-
-                let mut sexprs = args
-                    .iter()
-                    .map(|a| to_sexpr(&anno, a, matches.first().copied()))
-                    .collect::<Vec<SExpr>>();
-                sexprs.insert(0, self.smt.atom(var));
-                self.smt.list(sexprs)
             }
-            sema::Expr::Var(_, var_id) => {
-                let sym = rule.vars[var_id.index()].name;
-                let ident = typeenv.syms[sym.index()].clone();
-
-                let mut var = " ".to_string();
-                match parent_term {
-                    Some(value) => match value.var_to_type_var.get(&ident) {
-                        Some(ty) => {
-                            var =
-                                format!("[{}|{}]", type_to_num(type_sols.get(ty).unwrap()), ident);
-                        }
-                        None => {
-                            var = format!(
-                                "[{}|{}]",
-                                type_to_num(
-                                    type_sols
-                                        .get(&value.var_to_type_var.get("arg").unwrap())
-                                        .unwrap()
-                                ),
-                                ident
-                            );
-                        }
-                    },
-                    None => print!("Not found!"),
+            for (id, semantics) in &type_sols {
+                if let Some(rule_stats) = semantics.stats {
+                    let rule = &termenv.rules[id.index()];
+                    stats.push((rule_name(rule, tyenv), rule_stats));
                 }
-
-                self.smt.atom(var)
-            }
-            sema::Expr::ConstPrim(_, sym) => {
-                let name = typeenv.syms[sym.index()].clone();
-                self.smt.list(vec![self.smt.atom(name)])
-            }
-            sema::Expr::ConstInt(_, num) => {
-                let _smt_name_prefix = format!("{}__", num);
-                // TODO: look up BV vs int
-                self.smt.list(vec![self.smt.atom(num.to_string())])
-            }
-            sema::Expr::Let { bindings, body, .. } => {
-                let mut sexprs = vec![];
-                for (varid, _, expr) in bindings {
-                    let sym = rule.vars[varid.index()].name;
-                    let ident = typeenv.syms[sym.index()].clone();
-
-                    sexprs.push(self.smt.list(vec![
-                        self.smt.atom(ident),
-                        to_sexpr(annotation_infos, expr, parent_term),
-                    ]));
-                }
-                self.smt.list(vec![
-                    self.smt.atom("let"),
-                    self.smt.list(sexprs),
-                    to_sexpr(annotation_infos, body, parent_term),
-                ])
             }
         }
+        term_counts.push((term, typed - term_typed, selected - term_selected));
     }
-}
-
-#[derive(Clone)]
-struct SymbolicVariable {
-    name: String,
-    expr: SExpr,
-}
 
-impl SymbolicVariable {
-    fn integer(smt: &mut easy_smt::Context, name: String) -> Self {
-        Self::decl_sort(smt, name, smt.int_sort())
+    if args.all_terms {
+        for (term, term_typed, term_selected) in &term_counts {
+            config.reporter.result(format_args!(
+                "{}: {}/{} rules typed",
+                term, term_typed, term_selected
+            ));
+        }
     }
 
-    fn boolean(smt: &mut easy_smt::Context, name: String) -> Self {
-        Self::decl_sort(smt, name, smt.bool_sort())
+    if args.stats {
+        print_stats(&stats);
     }
 
-    fn decl_sort(smt: &mut easy_smt::Context, name: String, sort: SExpr) -> Self {
-        smt.declare_const(name.clone(), sort).unwrap();
-        let expr = smt.atom(name.clone());
-        Self { name, expr }
+    if let Some(path) = &args.json {
+        let file =
+            std::fs::File::create(output_path(path)).expect("Could not create JSON output file");
+        serde_json::to_writer_pretty(file, &json).expect("Could not write JSON output");
     }
-}
 
-#[derive(Clone)]
-struct SymbolicOption {
-    some: SymbolicVariable,
-    value: SymbolicVariable,
-}
-
-impl SymbolicOption {
-    fn decl(smt: &mut easy_smt::Context, value: SymbolicVariable) -> Self {
-        let some = SymbolicVariable::boolean(smt, format!("{}_some", value.name));
-        Self { some, value }
+    if let Some(path) = &args.csv {
+        std::fs::write(output_path(path), csv).expect("Could not write CSV output");
     }
-}
 
-#[derive(EnumIter, FromRepr, Debug)]
-enum TypeDiscriminant {
-    BitVector = 1,
-    Int = 2,
-    Bool = 3,
-}
-
-#[derive(Clone)]
-struct SymbolicType {
-    discriminant: SymbolicVariable,
-    bitvector_width: SymbolicOption,
-    integer_value: SymbolicOption,
-}
-
-impl SymbolicType {
-    fn decl(smt: &mut easy_smt::Context, v: u32) -> Self {
-        let prefix = format!("t{}", v);
-
-        // Disciminant.
-        let discriminant = SymbolicVariable::integer(smt, format!("{prefix}_disc"));
-
-        // Invariant: discriminant is one of the allowed values
-        smt.assert(smt.or_many(
-            TypeDiscriminant::iter().map(|d| smt.eq(discriminant.expr, smt.numeral(d as u8))),
-        ))
-        .unwrap();
-
-        // Bitvector width (option).
-        let bitvector_width_value =
-            SymbolicVariable::integer(smt, format!("{prefix}_bitvector_width"));
-        let bitvector_width = SymbolicOption::decl(smt, bitvector_width_value);
-
-        // Invariant: if not bitvector then bitvector width option is none.
-        smt.assert(smt.imp(
-            smt.distinct(
-                discriminant.expr,
-                smt.numeral(TypeDiscriminant::BitVector as u8),
-            ),
-            smt.not(bitvector_width.some.expr),
-        ))
-        .unwrap();
-
-        // Invariant: if bitvector width option is none, then its value is 0.
-        smt.assert(smt.imp(
-            smt.not(bitvector_width.some.expr),
-            smt.eq(bitvector_width.value.expr, smt.numeral(0)),
-        ))
-        .unwrap();
-
-        // Integer value.
-        let integer_value_value = SymbolicVariable::integer(smt, format!("{prefix}_integer_value"));
-        let integer_value = SymbolicOption::decl(smt, integer_value_value);
-
-        // Invariant: if not integer then integer value option is none.
-        smt.assert(smt.imp(
-            smt.distinct(discriminant.expr, smt.numeral(TypeDiscriminant::Int as u8)),
-            smt.not(integer_value.some.expr),
-        ))
-        .unwrap();
-
-        // Invariant: if integer value option is none, then its value is 0.
-        smt.assert(smt.imp(
-            smt.not(integer_value.some.expr),
-            smt.eq(integer_value.value.expr, smt.numeral(0)),
-        ))
-        .unwrap();
-
-        Self {
-            discriminant,
-            bitvector_width,
-            integer_value,
-        }
+    if let Some(path) = &args.emit_isle {
+        std::fs::write(output_path(path), isle).expect("Could not write ISLE output");
     }
-}
 
-fn main() {
-    let args = Args::parse();
-    let mut inputs = vec![];
-
-    let cur_dir = env::current_dir().expect("Can't access current working directory");
-    if !args.noprelude {
-        // Build the relevant ISLE prelude using the meta crate
-        inputs.push(build_clif_lower_isle());
-
-        // TODO: clean up path logic
-        inputs.push(cur_dir.join("./ref").join("inst_specs.isle"));
-        inputs.push(cur_dir.join("./ref").join("prelude.isle"));
-        inputs.push(cur_dir.join("./ref").join("prelude_lower.isle"));
+    // With an output directory, each rule's graph was written on its own
+    if let (Some(path), None) = (&args.dot, &output_dir) {
+        std::fs::write(path, dot).expect("Could not write DOT output");
     }
 
-    // DO NOT include these for broken tests
-    if args.aarch64 {
-        inputs.push(cur_dir.join("./ref/aarch64").join("inst.isle"));
-        inputs.push(cur_dir.join("./ref/aarch64").join("inst_specs.isle"));
-        inputs.push(cur_dir.join("./ref/aarch64").join("lower.isle"));
-    }
-
-    if let Some(i) = args.input {
-        inputs.push(PathBuf::from(i));
-    } else {
-        panic!("Missing input file in non-aarch64 mode");
+    // Checked after the outputs are written, so a failing run still has them
+    if args.only_failing && selected > typed {
+        std::process::exit(1);
     }
-
-    // Parse AST.
-    let ast = parse(Lexer::from_files(&inputs).unwrap()).expect("should parse");
-    // dbg!(&ast);
-    // Type Environment
-    let mut tyenv = TypeEnv::from_ast(&ast).expect("should not have type-definition errors");
-    // dbg!(&tyenv);
-    // Term Environment
-    let termenv: TermEnv =
-        TermEnv::from_ast(&mut tyenv, &ast, false).expect("should not have type-definition errors");
-    // dbg!(&termenv);
-
-    let annotation_env = parse_annotations(&ast, &termenv, &tyenv);
-
-    // let mut rule_names = ast
-    //     .defs
-    //     .iter()
-    //     .filter_map(|x| {
-    //         if let Def::Rule(value) = x {
-    //             Some(&value.name)
-    //         } else {
-    //             None
-    //         }
-    //     })
-    //     .map(|x| x.clone().expect("Isn't Ident").0.to_owned())
-    //     .collect::<Vec<_>>();
-    // rule_names.dedup();
-
-    let names = if let Some(names) = args.names {
-        let mut names = names;
-        names.sort();
-        names.dedup();
-        Some(names)
-    } else {
-        None
-    };
-
-    let config = Config {
-        term: args.term,
-        names: names,
-    };
-
-    // Get the types/widths for this particular term
-    let types = annotation_env
-        .get_term_signatures_by_name(&termenv, &tyenv)
-        .get(&config.term as &str)
-        .expect(format!("Missing term width for {}", config.term).as_str())
-        .clone();
-
-    for type_instantiation in types {
-        let _type_sols = type_rules_with_term_and_types(
-            &termenv,
-            &tyenv,
-            &annotation_env,
-            &config,
-            &type_instantiation,
-            &None,
-        );
-
-        // Old print method:
-        //     for rules in &type_sols {
-        //         for annotation in &rules.1.annotation_infos {
-        //             println!("\nTyping Rule for {}", annotation.term);
-        //             for var in &annotation.var_to_type_var {
-        //                 println!(
-        //                     "{}: {:?}",
-        //                     var.0,
-        //                     rules.1.type_var_to_type.get(var.1).unwrap()
-        //                 );
-        //             }
-        //         }
-        //     }
+    if args.fail_on_skip {
+        let skipped = selected - typed;
+        let summary = format!("{} rules typed, {} skipped", typed, skipped);
+        if skipped > 0 {
+            // Printed even when only failing rules are, since it's why the
+            // run fails
+            config.reporter.failure(summary);
+            std::process::exit(1);
+        }
+        config.reporter.result(summary);
     }
 }
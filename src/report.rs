@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+/// How much inference prints, from nothing at all to a trace of every term.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Print nothing, e.g. when used as a library
+    Silent,
+    /// Print only the typed rules
+    Quiet,
+    /// Also print skipped rules and progress
+    Normal,
+    /// Also trace each term as its rule is typed
+    Verbose,
+}
+
+/// Routes inference's output by verbosity.
+#[derive(Clone, Copy, Debug)]
+pub struct Reporter {
+    pub verbosity: Verbosity,
+    /// Print only rules that couldn't be typed, and why
+    pub only_failing: bool,
+}
+
+impl Reporter {
+    pub fn new(verbosity: Verbosity) -> Self {
+        Self {
+            verbosity,
+            only_failing: false,
+        }
+    }
+
+    /// The same reporter printing only the rules that couldn't be typed.
+    pub fn only_failing(self) -> Self {
+        Self {
+            only_failing: true,
+            ..self
+        }
+    }
+
+    /// The same reporter without per-term tracing, whose partial lines would
+    /// interleave across worker threads.
+    pub fn without_trace(self) -> Self {
+        Self {
+            verbosity: self.verbosity.min(Verbosity::Normal),
+            ..self
+        }
+    }
+
+    /// A typed rule or other result, printed unless silent.
+    pub fn result(&self, msg: impl Display) {
+        if self.verbosity >= Verbosity::Quiet && !self.only_failing {
+            println!("{}", msg);
+        }
+    }
+
+    /// A progress message.
+    pub fn info(&self, msg: impl Display) {
+        if self.verbosity >= Verbosity::Normal && !self.only_failing {
+            println!("{}", msg);
+        }
+    }
+
+    /// A rule that couldn't be typed, printed even when only failing rules
+    /// are.
+    pub fn failure(&self, msg: impl Display) {
+        if self.verbosity >= Verbosity::Normal || self.only_failing {
+            println!("{}", msg);
+        }
+    }
+
+    /// Part of a rule's trace. No newline is added, so the terms of a rule
+    /// share a line.
+    pub fn trace(&self, msg: impl Display) {
+        if self.verbosity >= Verbosity::Verbose && !self.only_failing {
+            print!("{}", msg);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Types the example files under `test/` and checks the types inferred for
+//! each rule against those given in the file's `Expected:` header.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use type_inf::inference::{rule_name, type_to_num};
+use type_inf::{
+    parse_program, Config, Error, Reporter, SolverKind, TargetConfig, TypeError, Verbosity,
+};
+
+// The options the command line defaults to, without an SMT log or output.
+fn config(term: &str) -> Config {
+    Config {
+        term: term.to_string(),
+        names: None,
+        names_regex: false,
+        exclude_names: vec![],
+        smt_log: None,
+        solver: SolverKind::Z3,
+        parallel: false,
+        reporter: Reporter::new(Verbosity::Silent),
+        timeout: None,
+        check_unique: false,
+        dump_constraints: false,
+        // The typed rule is rendered into `RuleSemantics::isle`
+        emit_isle: true,
+        emit_dot: false,
+        assume_identity: false,
+        stats: false,
+        target: TargetConfig::default(),
+        strict_conv_to: false,
+        switch_coverage: None,
+        narrow_shift_amounts: false,
+        max_width: 512,
+        poly_nullary: false,
+        explain: None,
+    }
+}
+
+// Type the rules rooted at `config.term` in the example files, by rule name.
+// A typed rule is given as its LHS and RHS with each node labeled
+// `[type|name]`, as the tool prints it and the `Expected:` headers give it.
+fn type_example(paths: &[&str], config: &Config) -> HashMap<String, Result<String, TypeError>> {
+    let paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path))
+        .collect();
+    let program = parse_program(&paths).unwrap();
+    let mut instantiations = program.try_infer(config).unwrap();
+    assert_eq!(instantiations.len(), 1, "examples have one instantiation");
+    instantiations
+        .pop()
+        .unwrap()
+        .into_iter()
+        .map(|(id, result)| {
+            let name = rule_name(&program.termenv.rules[id.index()], &program.typeenv);
+            let typed = result.map(|sem| {
+                sem.isle
+                    .unwrap()
+                    .lines()
+                    .filter_map(|line| line.strip_prefix(";; "))
+                    .filter(|line| !line.starts_with("(if-let"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+            (name, typed)
+        })
+        .collect()
+}
+
+// The only rule typed, for examples with one rule per root term.
+fn only_rule(paths: &[&str], config: &Config) -> Result<String, TypeError> {
+    let mut rules = type_example(paths, config);
+    assert_eq!(rules.len(), 1, "expected one rule, got {:?}", rules.keys());
+    rules.drain().next().unwrap().1
+}
+
+#[test]
+fn abs() {
+    assert_eq!(
+        only_rule(&["test/abs.isle"], &config("A")).unwrap(),
+        "([bv32|A] [bv32|x]) => ([bv32|B] [bv32|x])"
+    );
+    // Every width is settled without the solver, and still held to the bound
+    let narrow = Config {
+        max_width: 16,
+        ..config("A")
+    };
+    match only_rule(&["test/abs.isle"], &narrow) {
+        Err(TypeError::WidthTooLarge { width, max, .. }) => assert_eq!((width, max), (32, 16)),
+        other => panic!("expected a width beyond the bound, got {:?}", other),
+    }
+    let unique = Config {
+        check_unique: true,
+        ..config("A")
+    };
+    assert!(only_rule(&["test/abs.isle"], &unique).is_ok());
+}
+
+#[test]
+fn overflow() {
+    assert_eq!(
+        only_rule(&["test/overflow.isle"], &config("A")).unwrap(),
+        "([bool|A] [bv8|x] [bv8|y]) => ([bool|B] [bv8|x] [bv8|y])"
+    );
+    assert_eq!(
+        only_rule(&["test/overflow.isle"], &config("S")).unwrap(),
+        "([bool|S] [bv16|x] [bv16|y]) => ([bool|T] [bv16|x] [bv16|y])"
+    );
+}
+
+#[test]
+fn subs_flags() {
+    assert_eq!(
+        only_rule(&["test/subs_flags.isle"], &config("A")).unwrap(),
+        "([bv4|A] [bv64|x] [bv64|y]) => ([bv4|B] [bv64|x] [bv64|y])"
+    );
+}
+
+#[test]
+fn const_prim() {
+    assert_eq!(
+        only_rule(&["test/const_prim.isle"], &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|B] [bv8|Fast])"
+    );
+}
+
+#[test]
+fn split_files() {
+    let paths = ["test/split/terms.isle", "test/split/rules.isle"];
+    assert_eq!(
+        only_rule(&paths, &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|B] [bv8|x])"
+    );
+}
+
+#[test]
+fn concat() {
+    assert_eq!(
+        only_rule(&["test/concat.isle"], &config("A")).unwrap(),
+        "([bv16|A] [bv8|x] [bv8|y]) => ([bv16|B] [bv8|x] [bv8|y])"
+    );
+}
+
+#[test]
+fn bad_extract() {
+    let rules = type_example(&["test/bad_extract.isle"], &config("A"));
+    match &rules["swapped"] {
+        Err(TypeError::InvalidAnnotation { message, .. }) => {
+            assert_eq!(message, "extract 0 7 has its bounds swapped")
+        }
+        other => panic!("expected an invalid annotation, got {:?}", other),
+    }
+}
+
+#[test]
+fn free_width() {
+    assert_eq!(
+        only_rule(&["test/free_width.isle"], &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|B] [bv8|x] ([bv|C]))"
+    );
+}
+
+#[test]
+fn conv_width() {
+    assert_eq!(
+        only_rule(&["test/conv_width.isle"], &config("A")).unwrap(),
+        "([bv16|A] [bv8|x] [bv32|y]) => ([bv16|B] [bv8|x] [bv32|y])"
+    );
+    let rules = type_example(&["test/bad_conv_width.isle"], &config("A"));
+    match &rules["negative"] {
+        Err(TypeError::InvalidAnnotation { message, .. }) => {
+            assert_eq!(message, "conv_to width -8 is not a valid bitvector width")
+        }
+        other => panic!("expected an invalid annotation, got {:?}", other),
+    }
+}
+
+#[test]
+fn iflet_chain() {
+    assert_eq!(
+        only_rule(&["test/iflet_chain.isle"], &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|B] [bv8|z])"
+    );
+}
+
+#[test]
+fn many_assertions() {
+    assert_eq!(
+        only_rule(&["test/many_assertions.isle"], &config("A")).unwrap(),
+        "([bv16|A] [bv16|x] [bv16|y]) => ([bv16|B] [bv16|x] [bv16|y])"
+    );
+}
+
+#[test]
+fn bound_const() {
+    assert_eq!(
+        only_rule(&["test/bound_const.isle"], &config("A")).unwrap(),
+        "([bv8|A] ([int|w] @ [int|32]) [bv8|x]) => ([bv8|B] ([bv32|C] [int|w] [bv8|x]))"
+    );
+}
+
+#[test]
+fn rotate() {
+    assert_eq!(
+        only_rule(&["test/rotate.isle"], &config("A")).unwrap(),
+        "([bv32|A] [bv32|x] [bv32|y]) => ([bv32|B] [bv32|x] [bv32|y])"
+    );
+}
+
+#[test]
+fn dup_switch() {
+    let rules = type_example(&["test/dup_switch.isle"], &config("A"));
+    assert_eq!(
+        rules["dup"].as_ref().unwrap(),
+        "([bv2|A] [bv2|x]) => ([bv2|B] [bv2|x])"
+    );
+    let config = Config {
+        switch_coverage: Some(2),
+        ..config("A")
+    };
+    let rules = type_example(&["test/dup_switch.isle"], &config);
+    match &rules["dup"] {
+        Err(TypeError::IncompleteSwitch { width, missing, .. }) => {
+            assert_eq!((*width, missing.as_slice()), (2, &[3][..]))
+        }
+        other => panic!("expected an incomplete switch, got {:?}", other),
+    }
+}
+
+#[test]
+fn unit() {
+    assert_eq!(
+        only_rule(&["test/unit.isle"], &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => (let ((_ ([unit|E] [bv8|x]))) ([bv8|B] [bv8|x]))"
+    );
+}
+
+#[test]
+fn concat_int() {
+    let rules = type_example(&["test/concat_int.isle"], &config("A"));
+    match &rules["mixed"] {
+        Err(TypeError::InvalidAnnotation { message, .. }) => assert_eq!(
+            message,
+            "concat operand 1 is an integer; give it a width with int2bv"
+        ),
+        other => panic!("expected an invalid annotation, got {:?}", other),
+    }
+}
+
+#[test]
+fn sidecar() {
+    let path = |file: &str| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(file);
+    let mut program = parse_program(&[path("test/sidecar/rules.isle")]).unwrap();
+    match program.merge_annotation_file(&path("test/sidecar/unknown.isle")) {
+        Err(Error::Annotation(msg)) => assert!(
+            msg.ends_with("unknown.isle: spec for unknown term `Z`"),
+            "{}",
+            msg
+        ),
+        other => panic!("expected an unknown term, got {:?}", other),
+    }
+    program
+        .merge_annotation_file(&path("test/sidecar/annotations.isle"))
+        .unwrap();
+    match program.merge_annotation_file(&path("test/sidecar/duplicate.isle")) {
+        Err(Error::Annotation(msg)) => assert!(
+            msg.ends_with("duplicate.isle: term `B` has a spec in more than one file"),
+            "{}",
+            msg
+        ),
+        other => panic!("expected a duplicate spec, got {:?}", other),
+    }
+}
+
+#[test]
+fn let_mismatch() {
+    let rules = type_example(&["test/let_mismatch.isle"], &config("A"));
+    match &rules["widen"] {
+        Err(TypeError::LetTypeMismatch { var, core, .. }) => {
+            assert_eq!(var, "y");
+            assert!(!core.is_empty());
+        }
+        other => panic!("expected a let type mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn poly_nullary() {
+    let poly = Config {
+        poly_nullary: true,
+        ..config("A")
+    };
+    assert_eq!(
+        only_rule(&["test/poly_nullary.isle"], &poly).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|B] [bv8|x] ([bv8|Z]))"
+    );
+    match only_rule(&["test/poly_nullary.isle"], &config("A")) {
+        Err(TypeError::UnannotatedTerm { term }) => assert_eq!(term, "Z"),
+        other => panic!("expected an unannotated term, got {:?}", other),
+    }
+}
+
+#[test]
+fn bind_mismatch() {
+    let rules = type_example(&["test/bind_mismatch.isle"], &config("A"));
+    match &rules["mismatch"] {
+        Err(TypeError::BindMismatch {
+            var,
+            bound,
+            pattern,
+            ..
+        }) => {
+            assert_eq!(var, "x");
+            assert_eq!(type_to_num(bound), "bv16");
+            assert_eq!(type_to_num(pattern), "bv8");
+        }
+        other => panic!("expected a bind mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn two_outputs() {
+    assert_eq!(
+        only_rule(&["test/two_outputs.isle"], &config("A")).unwrap(),
+        "([bv16|A] ([bv16|P] [bv8|hi] [bv8|lo])) => ([bv16|B] [bv8|hi] [bv8|lo])"
+    );
+}
+
+#[test]
+fn narrow_shift() {
+    let narrow = Config {
+        narrow_shift_amounts: true,
+        ..config("A")
+    };
+    assert_eq!(
+        only_rule(&["test/narrow_shift.isle"], &narrow).unwrap(),
+        "([bv32|A] [bv32|x] [bv8|n]) => ([bv32|B] [bv32|x] [bv8|n])"
+    );
+    assert!(matches!(
+        only_rule(&["test/narrow_shift.isle"], &config("A")),
+        Err(TypeError::Unsat { .. })
+    ));
+    // Rotate amounts are held to the same width as shift amounts
+    let narrow_rotate = Config {
+        narrow_shift_amounts: true,
+        ..config("S")
+    };
+    assert_eq!(
+        only_rule(&["test/narrow_shift.isle"], &narrow_rotate).unwrap(),
+        "([bv32|S] [bv32|x] [bv8|n]) => ([bv32|R] [bv32|x] [bv8|n])"
+    );
+    assert!(matches!(
+        only_rule(&["test/narrow_shift.isle"], &config("S")),
+        Err(TypeError::Unsat { .. })
+    ));
+}
+
+#[test]
+fn mangled_names() {
+    assert_eq!(
+        only_rule(&["test/mangled_names.isle"], &config("A__B")).unwrap(),
+        "([bv8|A__B] [bv8|x] [bv8|x__clif0]) => ([bv8|C] [bv8|x] [bv8|x__clif0])"
+    );
+}
+
+#[test]
+fn bool_const() {
+    assert_eq!(
+        only_rule(&["test/bool_const.isle"], &config("A")).unwrap(),
+        "([bv8|A] [bv8|x]) => ([bv8|C] [bool|true] [bv8|x])"
+    );
+}
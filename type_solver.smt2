@@ -1,2 +0,0 @@
-(set-option :print-success true)
-(set-option :produce-models true)